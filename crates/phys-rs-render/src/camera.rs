@@ -0,0 +1,73 @@
+use crate::vec2::Vector2;
+
+const MIN_ZOOM: f32 = 0.01;
+const MAX_ZOOM: f32 = 100.0;
+
+// ====< CAMERA >====
+/// Maps scene pixels (world coordinates after `Units` are applied) to the screen.
+/// The default camera maps them 1:1, zoom and rotation pivot around the center of the screen.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Camera2D {
+    // Pan offset in pixels
+    pub position: Vector2,
+    pub zoom: f32,
+    // Positive values turn the world counter-clockwise on screen
+    pub rotation: f32,
+}
+
+impl Default for Camera2D {
+    fn default() -> Self {
+        Self {
+            position: Vector2::zero(),
+            zoom: 1.0,
+            rotation: 0.0,
+        }
+    }
+}
+
+impl Camera2D {
+    pub fn new(position: Vector2, zoom: f32, rotation: f32) -> Self {
+        Self { position, zoom, rotation }
+    }
+
+    pub fn world_to_screen(&self, point: Vector2, resolution: Vector2) -> Vector2 {
+        let half = resolution / 2.0;
+        // Screen y points down, so counter-clockwise on screen is clockwise in math terms
+        (point - self.position - half).rotate(-self.rotation) * self.zoom + half
+    }
+
+    pub fn screen_to_world(&self, point: Vector2, resolution: Vector2) -> Vector2 {
+        let half = resolution / 2.0;
+        ((point - half) / self.zoom).rotate(self.rotation) + half + self.position
+    }
+
+    // Moves the camera so the world follows a drag of `delta` pixels
+    pub fn pan(&mut self, delta: Vector2) {
+        self.position -= (delta / self.zoom).rotate(self.rotation);
+    }
+
+    // Zooms by `factor` while keeping the world point under `anchor` (in pixels) in place
+    pub fn zoom_at(&mut self, anchor: Vector2, factor: f32, resolution: Vector2) {
+        let before = self.screen_to_world(anchor, resolution);
+        self.zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+        let after = self.screen_to_world(anchor, resolution);
+        self.position += before - after;
+    }
+
+    // Same transform as `world_to_screen`, column major
+    pub fn matrix(&self, resolution: Vector2) -> [f32; 16] {
+        let half = resolution / 2.0;
+        let (sin, cos) = self.rotation.sin_cos();
+        let (a, b, c, d) = (self.zoom * cos, -self.zoom * sin, self.zoom * sin, self.zoom * cos);
+
+        let origin = self.position + half;
+        let e = half.x - (a * origin.x + c * origin.y);
+        let f = half.y - (b * origin.x + d * origin.y);
+        [
+            a, b, 0.0, 0.0,
+            c, d, 0.0, 0.0,
+            0.0, 0.0, 1.0, 0.0,
+            e, f, 0.0, 1.0,
+        ]
+    }
+}
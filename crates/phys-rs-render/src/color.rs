@@ -28,6 +28,21 @@ impl Color {
         Self { r, g, b, a }
     }
 
+    // Straight blend in sRGB, `t` is clamped to [0, 1]
+    pub fn lerp(&self, other: Color, t: f32) -> Self {
+        let t = t.clamp(0.0, 1.0);
+        Self {
+            r: self.r + (other.r - self.r) * t,
+            g: self.g + (other.g - self.g) * t,
+            b: self.b + (other.b - self.b) * t,
+            a: self.a + (other.a - self.a) * t,
+        }
+    }
+
+    pub fn with_alpha(&self, a: f32) -> Self {
+        Self { a, ..*self }
+    }
+
     #[allow(clippy::wrong_self_convention)]
     pub fn to_linear_rgb(&self) -> [f32; 4] {
         let f = |xu: u32| {
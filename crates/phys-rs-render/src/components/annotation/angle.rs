@@ -0,0 +1,105 @@
+use crate::{Brush, math::Vector2, color::Color, ColorPalette, text::TextAnchor};
+
+const LABEL_OFFSET: f32 = 12.0;
+const TICK_LENGTH: f32 = 8.0;
+const TICK_SPACING: f32 = 0.12; // radians between tick marks
+const SEGMENT_LENGTH: f32 = 4.0; // pixels of arc per line segment
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AngleLabel {
+    None,
+    Degrees,
+    Radians,
+}
+
+// Sizes are in pixels, whatever the scene units are
+pub struct AngleStyle {
+    pub radius: f32,
+    pub color: Color,
+    pub thickness: f32,
+    // Number of tick marks across the arc, used to mark equal angles
+    pub ticks: u32,
+    pub fill: Option<Color>,
+    pub label: AngleLabel,
+    pub label_size: f32,
+    pub decimals: usize,
+}
+
+impl Default for AngleStyle {
+    fn default() -> Self {
+        Self {
+            radius: 40.0,
+            color: ColorPalette::WHITE,
+            thickness: 2.0,
+            ticks: 0,
+            fill: None,
+            label: AngleLabel::Degrees,
+            label_size: 14.0,
+            decimals: 0,
+        }
+    }
+}
+
+// Draws the (smaller) angle between `from` and `to` around `vertex` and returns it in radians
+pub fn draw_angle(brush: &mut Brush, vertex: Vector2, from: Vector2, to: Vector2, style: &AngleStyle) -> f32 {
+    let start = from.angle();
+    let sweep = from.angle_to(to);
+    if !sweep.is_finite() {
+        return 0.0;
+    }
+
+    let radius = brush.px(style.radius);
+    let thickness = brush.px(style.thickness);
+    let segments = ((style.radius * sweep.abs() / SEGMENT_LENGTH).ceil() as usize).max(2);
+    let point = |t: f32, radius: f32| vertex + Vector2::from_angle(start + sweep * t) * radius;
+
+    // Filled sector
+    if let Some(fill) = style.fill {
+        let points: Vec<Vector2> = std::iter::once(vertex)
+            .chain((0..=segments).map(|i| point(i as f32 / segments as f32, radius)))
+            .collect();
+        brush.draw_polygon_filled(&points, fill);
+    }
+
+    // Arc
+    for i in 0..segments {
+        let a = point(i as f32 / segments as f32, radius);
+        let b = point((i + 1) as f32 / segments as f32, radius);
+        brush.draw_line_rounded(a, b, thickness, style.color);
+    }
+
+    // Tick marks, centered on the bisector
+    for i in 0..style.ticks {
+        let offset = (i as f32 - (style.ticks - 1) as f32 / 2.0) * TICK_SPACING * sweep.signum();
+        let t = (0.5 + offset / sweep).clamp(0.0, 1.0);
+        let inner = point(t, radius - brush.px(TICK_LENGTH / 2.0));
+        let outer = point(t, radius + brush.px(TICK_LENGTH / 2.0));
+        brush.draw_line(inner, outer, thickness, style.color);
+    }
+
+    // Label on the bisector
+    let text = match style.label {
+        AngleLabel::None => None,
+        AngleLabel::Degrees => Some(format!("{:.*}°", style.decimals, sweep.abs().to_degrees())),
+        AngleLabel::Radians => Some(format!("{:.*} rad", style.decimals.max(2), sweep.abs())),
+    };
+    if let Some(text) = text {
+        brush.draw_text(&text, point(0.5, radius + brush.px(LABEL_OFFSET)), brush.px(style.label_size), style.color, TextAnchor::Center, 0.0);
+    }
+
+    sweep
+}
+
+// Angle between two segments sharing `vertex`
+pub fn draw_angle_between(brush: &mut Brush, vertex: Vector2, a: Vector2, b: Vector2, style: &AngleStyle) -> f32 {
+    draw_angle(brush, vertex, a - vertex, b - vertex, style)
+}
+
+// Right angle marker, a small square instead of an arc, sizes in pixels
+pub fn draw_right_angle(brush: &mut Brush, vertex: Vector2, from: Vector2, to: Vector2, size: f32, thickness: f32, color: Color) {
+    let thickness = brush.px(thickness);
+    let a = from.normalize() * brush.px(size);
+    let b = to.normalize() * brush.px(size);
+    brush.draw_line(vertex + a, vertex + a + b, thickness, color);
+    brush.draw_line(vertex + b, vertex + a + b, thickness, color);
+}
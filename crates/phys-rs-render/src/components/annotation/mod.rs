@@ -0,0 +1,3 @@
+mod angle;
+
+pub use angle::{draw_angle, draw_angle_between, draw_right_angle, AngleStyle, AngleLabel};
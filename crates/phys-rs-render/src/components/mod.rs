@@ -1,2 +1,3 @@
 pub mod ui;
-pub mod physics;
\ No newline at end of file
+pub mod physics;
+pub mod annotation;
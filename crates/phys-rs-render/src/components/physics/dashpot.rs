@@ -0,0 +1,62 @@
+use crate::{Brush, math::Vector2, color::Color, ColorPalette};
+
+// Sizes are in pixels, whatever the scene units are
+#[derive(Debug, Clone, PartialEq)]
+pub struct DashpotStyle {
+    pub width: f32,
+    pub cylinder_length: f32,
+    pub thickness: f32,
+    pub connector_length: f32,
+    pub color: Color,
+    // Inside of the cylinder
+    pub fill: Color,
+}
+
+impl Default for DashpotStyle {
+    fn default() -> Self {
+        Self {
+            width: 20.0,
+            cylinder_length: 36.0,
+            thickness: 2.5,
+            connector_length: 15.0,
+            color: ColorPalette::WHITE,
+            fill: ColorPalette::BACKGROUND,
+        }
+    }
+}
+
+impl DashpotStyle {
+    pub fn with_color(self, color: Color) -> Self {
+        Self { color, ..self }
+    }
+}
+
+// Damper from `a` (cylinder) to `b` (piston). The piston sits in the middle of the cylinder at `rest_length`
+pub fn draw_dashpot(brush: &mut Brush, a: Vector2, b: Vector2, rest_length: f32, style: &DashpotStyle) {
+    let length = (b - a).length();
+    if length <= f32::EPSILON {
+        return;
+    }
+    let dir = (b - a) / length;
+    let side = Vector2::new(-dir.y, dir.x) * (brush.px(style.width) / 2.0);
+    let thickness = brush.px(style.thickness);
+
+    // Shrink everything on very short dampers
+    let connector = brush.px(style.connector_length).min(length * 0.2);
+    let cylinder = brush.px(style.cylinder_length).min(length * 0.6);
+    let (bottom, top) = (a + dir * connector, a + dir * (connector + cylinder));
+
+    // Cylinder, open towards the piston
+    brush.draw_line_rounded(a, bottom, thickness, style.color);
+    brush._draw_quad_border_raw((bottom + top) / 2.0, Vector2::new(cylinder, brush.px(style.width)), style.fill, 0.0, style.fill, -dir.angle(), 0.0);
+    brush.draw_line_rounded(bottom - side, bottom + side, thickness, style.color);
+    brush.draw_line_rounded(bottom - side, top - side, thickness, style.color);
+    brush.draw_line_rounded(bottom + side, top + side, thickness, style.color);
+
+    // Piston moves with the extension, but never leaves the cylinder
+    let margin = thickness * 2.0;
+    let travel = (cylinder / 2.0 + length - rest_length).clamp(margin, (cylinder - margin).max(margin));
+    let piston = bottom + dir * travel;
+    brush.draw_line_rounded(piston, b, thickness, style.color);
+    brush.draw_line_rounded(piston - side * 0.75, piston + side * 0.75, thickness * 1.5, style.color);
+}
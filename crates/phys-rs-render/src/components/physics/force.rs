@@ -0,0 +1,68 @@
+use crate::{Brush, math::Vector2, color::Color, ColorPalette, text::TextAnchor};
+
+const LABEL_OFFSET: f32 = 6.0;
+
+// Sizes are in pixels, whatever the scene units are
+pub struct ForceStyle {
+    // Pixels per unit of force
+    pub scale: f32,
+    pub color: Color,
+    pub thickness: f32,
+    pub head_size: f32,
+    pub label: Option<String>,
+    pub label_size: f32,
+}
+
+impl Default for ForceStyle {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            color: ColorPalette::RED,
+            thickness: 3.0,
+            head_size: 12.0,
+            label: None,
+            label_size: 16.0,
+        }
+    }
+}
+
+impl ForceStyle {
+    pub fn new(scale: f32, color: Color) -> Self {
+        Self {
+            scale,
+            color,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_label(self, label: &str) -> Self {
+        Self {
+            label: Some(label.to_string()),
+            ..self
+        }
+    }
+}
+
+// Draws `force` as an arrow starting at its point of application
+pub fn draw_force(brush: &mut Brush, origin: Vector2, force: Vector2, style: &ForceStyle) {
+    let tip = origin + force * brush.px(style.scale);
+    brush.draw_arrow(origin, tip, brush.px(style.thickness), brush.px(style.head_size), style.color);
+
+    let length = (tip - origin).length();
+    if let (Some(label), true) = (&style.label, length > f32::EPSILON) {
+        let dir = (tip - origin) / length;
+
+        // Keep the label clear of the arrow head, on the side the arrow points to
+        let down = dir.y * brush.units.y_sign();
+        let anchor = if dir.x > 0.38 {
+            TextAnchor::CenterLeft
+        } else if dir.x < -0.38 {
+            TextAnchor::CenterRight
+        } else if down > 0.0 {
+            TextAnchor::TopCenter
+        } else {
+            TextAnchor::BottomCenter
+        };
+        brush.draw_text(label, tip + dir * brush.px(LABEL_OFFSET), brush.px(style.label_size), style.color, anchor, 0.0);
+    }
+}
@@ -1,3 +1,9 @@
 mod spring;
+mod force;
+mod wall;
+mod dashpot;
 
-pub use spring::draw_spring;
\ No newline at end of file
+pub use spring::{draw_spring, SpringStyle, SpringKind, TensionColors};
+pub use dashpot::{draw_dashpot, DashpotStyle};
+pub use force::{draw_force, ForceStyle};
+pub use wall::{draw_wall, Wall, WallStyle};
@@ -1,91 +1,145 @@
-use crate::{Brush, math::Vector2, color::Color, ColorPalette, Renderer};
-
-const DEFAULT_SPRING_WIDTH: f32 = 30.0;
-const CONNECTOR_LENGTH: f32 = 15.0;
-const BORDER_THICKNESS: f32 = 1.5;
-const DEFAULT_ANGLE: f32 = 1.22; // about 70 degrees
-
-pub fn draw_spring(brush: &mut Brush, renderer: &mut Renderer, a: Vector2, b: Vector2, k: f32, l0: f32, scale: f32) {
-    let l = (a - b).length();
-    let f = k * (l - l0);
-    let dir = (b - a).normalize();
-    let angle = -dir.angle();
-
-    let x = l/l0;
-
-    // Draw basic line
-    brush.draw_line(a, b, 5.0, Color::from_hex(0xeeeeee10));
-    
-    // Calculate number of segments with length of l0 and default angle
-    let l0a = l0 - CONNECTOR_LENGTH*scale*2.0;
-    let segment_length = (DEFAULT_SPRING_WIDTH * scale / DEFAULT_ANGLE.sin()).abs();
-    let segment_x_length = segment_length * DEFAULT_ANGLE.cos();
-    let segment_count = (l0a / segment_x_length).floor() as u32;
-
-
-    let la = l - CONNECTOR_LENGTH * scale *2.0;
-    let rsl = la / segment_count as f32;
-    let current_angle = (rsl / segment_length).acos();
-    let current_segment_x_length = segment_length * current_angle.cos();
-    let current_segment_y_length = segment_length * current_angle.sin();
-
-    // Draw first segment (half length)
-    let side_seg_trans = dir * (current_segment_x_length * 0.25) + dir.rot_90cw() * (current_segment_y_length * 0.25 - 2.0);
-    brush._draw_quad_border_raw(a + dir * CONNECTOR_LENGTH * scale + side_seg_trans, 
-        Vector2::new(segment_length * 0.5, 6.5 * scale),
-        ColorPalette::WHITE, 
-        BORDER_THICKNESS * scale,
-        ColorPalette::BLACK,
-        current_angle + angle, 0.0);
-
-    // Draw segments
-
-    for i in 0..segment_count-1 {
-        let seg_pos = a + dir * CONNECTOR_LENGTH * scale + dir * current_segment_x_length * (i as f32) + dir * (current_segment_x_length);
-        let seg_ang = current_angle * ((-1.0) + ((i%2) as f32) * 2.0);
-        brush._draw_quad_border_raw(seg_pos, 
-            Vector2::new(segment_length, 6.5 * scale),
-            ColorPalette::WHITE, 
-            BORDER_THICKNESS * scale,
-            ColorPalette::BLACK,
-            seg_ang + angle, 0.0);
+use crate::{Brush, math::Vector2, color::Color, ColorPalette};
+
+const HELIX_SAMPLES_PER_COIL: u32 = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpringKind {
+    Zigzag,
+    // Side view of a coil, the back half is drawn dimmer
+    Helix,
+}
+
+// Blends the spring color towards `stretched` or `compressed` as the tension grows
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TensionColors {
+    pub stretched: Color,
+    pub compressed: Color,
+    // Tension at which the color is fully blended
+    pub reference: f32,
+}
+
+// Sizes are in pixels, whatever the scene units are
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpringStyle {
+    pub kind: SpringKind,
+    pub coils: u32,
+    pub width: f32,
+    pub thickness: f32,
+    // Straight part at both ends, shortened when the spring is very short
+    pub connector_length: f32,
+    pub color: Color,
+    // Dots at the attachment points, `None` skips them
+    pub end_color: Option<Color>,
+    pub end_radius: f32,
+    pub tension_colors: Option<TensionColors>,
+}
+
+impl Default for SpringStyle {
+    fn default() -> Self {
+        Self {
+            kind: SpringKind::Zigzag,
+            coils: 8,
+            width: 24.0,
+            thickness: 2.5,
+            connector_length: 15.0,
+            color: ColorPalette::WHITE,
+            end_color: Some(ColorPalette::WHITE),
+            end_radius: 3.5,
+            tension_colors: None,
+        }
+    }
+}
+
+impl SpringStyle {
+    pub fn new(kind: SpringKind, coils: u32) -> Self {
+        Self {
+            kind,
+            coils,
+            ..Default::default()
+        }
     }
 
-    // Draw last segment (half length)
-    brush._draw_quad_border_raw(b - dir * CONNECTOR_LENGTH * scale - side_seg_trans, 
-        Vector2::new(segment_length * 0.5, 6.5 * scale),
-        ColorPalette::WHITE, 
-        BORDER_THICKNESS * scale,
-        ColorPalette::BLACK,
-        current_angle + angle, 0.0);
-    
-
-    // Draw connector between first point and base
-    brush.draw_line_rounded(a - dir * 3.0, a + dir * CONNECTOR_LENGTH * scale, 6.0, ColorPalette::WHITE);
-
-    // Draw spring base 
-    brush._draw_quad_border_raw(a + dir * CONNECTOR_LENGTH * scale, 
-        Vector2::new(DEFAULT_SPRING_WIDTH * scale, 6.5 * scale),
-        ColorPalette::WHITE, 
-        BORDER_THICKNESS * scale,
-        ColorPalette::BLACK,
-        angle + 90f32.to_radians(), 0.0);
-
-    // Draw connector between second point and base
-    brush.draw_line_rounded(b + dir * 3.0, b - dir * CONNECTOR_LENGTH * scale, 6.0, ColorPalette::WHITE);
-
-    // Draw spring base
-    brush._draw_quad_border_raw(b - dir * CONNECTOR_LENGTH * scale, 
-        Vector2::new(DEFAULT_SPRING_WIDTH * scale, 6.5 * scale),
-        ColorPalette::WHITE, 
-        BORDER_THICKNESS,
-        ColorPalette::BLACK,
-        angle + 90f32.to_radians(), 0.0);
+    pub fn with_color(self, color: Color) -> Self {
+        Self { color, ..self }
+    }
 
-    brush.flush(renderer);
+    // Red when stretched and blue when compressed, fully at `reference` tension
+    pub fn with_tension_colors(self, reference: f32) -> Self {
+        Self {
+            tension_colors: Some(TensionColors { stretched: ColorPalette::RED, compressed: ColorPalette::BLUE, reference }),
+            ..self
+        }
+    }
 
-    // Draw circles at connection points
-    brush.draw_circle_filled(a, 2.5 * scale, ColorPalette::BLACK);
-    brush.draw_circle_filled(b, 2.5 * scale, ColorPalette::BLACK);
+    pub fn color_for(&self, tension: f32) -> Color {
+        match self.tension_colors {
+            Some(colors) if colors.reference > 0.0 => {
+                let target = if tension >= 0.0 { colors.stretched } else { colors.compressed };
+                self.color.lerp(target, tension.abs() / colors.reference)
+            }
+            _ => self.color,
+        }
+    }
+}
+
+// Spring from `a` to `b`, `tension` only picks the color. Works for any length, including zero
+pub fn draw_spring(brush: &mut Brush, a: Vector2, b: Vector2, tension: f32, style: &SpringStyle) {
+    let color = style.color_for(tension);
+    let thickness = brush.px(style.thickness);
+    let length = (b - a).length();
+
+    if length > f32::EPSILON {
+        let dir = (b - a) / length;
+        let side = Vector2::new(-dir.y, dir.x) * (brush.px(style.width) / 2.0);
+
+        // Connectors take at most a fifth of the spring each
+        let connector = brush.px(style.connector_length).min(length * 0.2);
+        let (start, end) = (a + dir * connector, b - dir * connector);
+        brush.draw_line_rounded(a, start, thickness, color);
+        brush.draw_line_rounded(end, b, thickness, color);
+
+        let coils = style.coils.max(1);
+        match style.kind {
+            SpringKind::Zigzag => {
+                // Two peaks per coil, halfway between the ends of each half coil
+                let peaks = coils * 2;
+                let mut previous = start;
+                for i in 0..peaks {
+                    let t = (i as f32 + 0.5) / peaks as f32;
+                    let sign = if i % 2 == 0 { 1.0 } else { -1.0 };
+                    let point = start + (end - start) * t + side * sign;
+                    brush.draw_line_rounded(previous, point, thickness, color);
+                    previous = point;
+                }
+                brush.draw_line_rounded(previous, end, thickness, color);
+            }
+            SpringKind::Helix => {
+                let samples = coils * HELIX_SAMPLES_PER_COIL;
+                let point = |i: u32| {
+                    let t = i as f32 / samples as f32;
+                    let phase = t * coils as f32 * std::f32::consts::TAU;
+                    // The coil leans back a little so both halves are visible, zero at both ends
+                    let lean = (end - start) * (0.25 / coils as f32) * (phase.cos() - 1.0);
+                    (start + (end - start) * t + side * phase.sin() + lean, phase.cos() < 0.0)
+                };
+
+                // Back half first, so the front covers it
+                for back in [true, false] {
+                    let color = if back { color.with_alpha(color.a * 0.4) } else { color };
+                    for i in 0..samples {
+                        let ((p0, behind), (p1, _)) = (point(i), point(i + 1));
+                        if behind == back {
+                            brush.draw_line_rounded(p0, p1, thickness, color);
+                        }
+                    }
+                }
+            }
+        }
+    }
 
-}
\ No newline at end of file
+    if let Some(end_color) = style.end_color {
+        let radius = brush.px(style.end_radius);
+        brush.draw_circle_filled(a, radius, end_color);
+        brush.draw_circle_filled(b, radius, end_color);
+    }
+}
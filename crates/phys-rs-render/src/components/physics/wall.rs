@@ -0,0 +1,124 @@
+use crate::{Brush, math::Vector2, color::Color, ColorPalette, Renderer, PhysRenderable, DataCollector, physics::{RigidBody, Shape}};
+
+// Sizes are in pixels, whatever the scene units are
+pub struct WallStyle {
+    pub color: Color,
+    pub thickness: f32,
+    // Covers the grid behind the hatching
+    pub fill: Color,
+    pub hatch_color: Color,
+    pub hatch_spacing: f32,
+    pub hatch_length: f32,
+    pub hatch_thickness: f32,
+}
+
+impl Default for WallStyle {
+    fn default() -> Self {
+        Self {
+            color: ColorPalette::WHITE,
+            thickness: 3.0,
+            fill: ColorPalette::BACKGROUND,
+            hatch_color: ColorPalette::WHITE,
+            hatch_spacing: 10.0,
+            hatch_length: 12.0,
+            hatch_thickness: 1.5,
+        }
+    }
+}
+
+// ====< WALL >====
+/// Immovable surface from `start` to `end`, the solid side is the one `normal` points away from.
+/// Add it with `Scene::add_wall` so that rigid bodies collide with it.
+pub struct Wall {
+    pub start: Vector2,
+    pub end: Vector2,
+    // Unit vector towards the free side
+    pub normal: Vector2,
+    // Thickness of the collider behind the surface, in world units
+    pub depth: f32,
+    pub style: WallStyle,
+}
+
+impl Wall {
+    pub fn create(start: Vector2, end: Vector2, normal: Vector2) -> Self {
+        assert!((end - start).length() > f32::EPSILON, "wall start and end are the same point");
+        let direction = (end - start).normalize();
+        // Only the side matters, make it perpendicular to the surface
+        let perpendicular = Vector2::new(-direction.y, direction.x);
+        let normal = if perpendicular.dot(normal) >= 0.0 { perpendicular } else { -perpendicular };
+
+        Self {
+            start,
+            end,
+            normal,
+            depth: (end - start).length() / 10.0,
+            style: WallStyle::default(),
+        }
+    }
+
+    pub fn with_depth(self, depth: f32) -> Self {
+        Self { depth, ..self }
+    }
+
+    pub fn with_style(self, style: WallStyle) -> Self {
+        Self { style, ..self }
+    }
+
+    pub fn length(&self) -> f32 {
+        (self.end - self.start).length()
+    }
+
+    // Point on the surface, 0 is `start` and 1 is `end`. Useful as a spring anchor
+    pub fn point_at(&self, t: f32) -> Vector2 {
+        self.start + (self.end - self.start) * t
+    }
+
+    pub fn center(&self) -> Vector2 {
+        self.point_at(0.5)
+    }
+
+    // Static collider filling `depth` behind the surface, drawn by the wall itself
+    pub fn body(&self) -> RigidBody {
+        let position = self.center() - self.normal * (self.depth / 2.0);
+        RigidBody::fixed(Shape::rectangle(self.length(), self.depth), position)
+            .with_angle((self.end - self.start).angle())
+            .hidden()
+    }
+}
+
+impl PhysRenderable for Wall {
+    fn render(&self, brush: &mut Brush, _renderer: &mut Renderer, _dt: f32, _alpha: f32, _frame: u128) {
+        draw_wall(brush, self.start, self.end, self.normal, &self.style);
+    }
+
+    fn update(&mut self, _dt: f32, _frame: u128, _data_collector: Option<&mut DataCollector>) {}
+}
+
+// Surface line with diagonal hatching on the solid side, the one `normal` points away from
+pub fn draw_wall(brush: &mut Brush, start: Vector2, end: Vector2, normal: Vector2, style: &WallStyle) {
+    let length = (end - start).length();
+    if length <= f32::EPSILON {
+        return;
+    }
+    let direction = (end - start) / length;
+    let normal = normal.normalize();
+    let hatch_length = brush.px(style.hatch_length);
+
+    // Background of the hatched band
+    let center = (start + end) / 2.0 - normal * (hatch_length / 2.0);
+    brush._draw_quad_border_raw(center, Vector2::new(length, hatch_length), style.fill, 0.0, style.fill, -direction.angle(), 0.0);
+
+    // Hatching at 45 degrees, the first lines are cut short so nothing sticks out past `start`
+    let spacing = brush.px(style.hatch_spacing).max(f32::EPSILON);
+    let slant = -normal - direction;
+    let mut u = spacing / 2.0;
+    while u <= length {
+        let a = start + direction * u;
+        let t = (u / hatch_length).min(1.0);
+        brush.draw_line(a, a + slant * (hatch_length * t), brush.px(style.hatch_thickness), style.hatch_color);
+        u += spacing;
+    }
+
+    // Surface
+    brush.draw_line_rounded(start, end, brush.px(style.thickness), style.color);
+}
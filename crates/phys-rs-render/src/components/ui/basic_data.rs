@@ -6,12 +6,14 @@
     - Delta time
     - Frame count
     - Draw calls count
+    - Broad phase stats
 */
 
-use egui::{Align, Align2, Vec2};
+use egui::{Align2, Vec2};
 
-use crate::{Renderer, EguiUI, PhysApp};
+use crate::{Renderer, EguiUI, DataCollector, Units};
 
+#[derive(Default)]
 pub struct BasicDataUI { }
 
 impl BasicDataUI {
@@ -21,7 +23,7 @@ impl BasicDataUI {
 }
 
 impl EguiUI for BasicDataUI {
-    fn ui(&mut self, ctx: &egui::Context, renderer: &Renderer) {
+    fn ui(&mut self, ctx: &egui::Context, renderer: &Renderer, _data_collector: &DataCollector) {
         // window
         egui::Window::new("Debug data").anchor(Align2::RIGHT_TOP, Vec2::new(-5.0, 5.0)).show(ctx, |ui| {
             // UI
@@ -39,6 +41,16 @@ impl EguiUI for BasicDataUI {
             // draw calls count
             ui.label(format!("Draw calls: {}", renderer.draw_calls + 1));
 
+            // grid spacing
+            if let Some(grid) = renderer.brush.as_ref().and_then(|brush| brush.grid_pipeline.grids().first()) {
+                let spacing = if renderer.units == Units::pixels() {
+                    format!("{} px", grid.spacing)
+                } else {
+                    Units::format_length(grid.spacing)
+                };
+                ui.label(format!("Grid: {}", spacing));
+            }
+
             // Physics
             ui.separator();
             ui.heading("Simulation");
@@ -49,6 +61,18 @@ impl EguiUI for BasicDataUI {
             // Average update time
             ui.label(format!("Average update time: {:.8}", renderer.avg_update_time));
 
+            // Broad phase
+            let counters = renderer.collision_counters;
+            if counters.bodies > 0 {
+                ui.separator();
+                ui.heading("Collisions");
+                ui.label(format!("Broad phase: {}", counters.broad_phase));
+                ui.label(format!("Bodies: {}", counters.bodies));
+                ui.label(format!("Candidate pairs: {} / {}", counters.candidate_pairs, counters.all_pairs));
+                ui.label(format!("Collisions: {}", counters.collisions));
+                ui.label(format!("Broad phase time: {:.8}", counters.broad_phase_time));
+            }
+
         });
     }
 }
@@ -0,0 +1,136 @@
+/*
+    DATA PLOT UI
+    - Selected DataCollector channels plotted against time
+    - Channel picker
+    - Rolling window length
+    - Pause (freezes the plotted data)
+*/
+
+use egui::plot::{Plot, Line, PlotPoints, Legend};
+
+use crate::{Renderer, EguiUI, DataCollector};
+
+const DEFAULT_WINDOW: f32 = 10.0;
+// Channels longer than this are thinned out before plotting
+const MAX_PLOTTED_POINTS: usize = 4000;
+
+pub struct DataPlotUI {
+    // Also identifies the window and the plot, so every DataPlotUI needs its own
+    pub title: String,
+    pub selected: Vec<String>,
+    // Length of the rolling window in seconds of simulated time
+    pub window: f32,
+
+    // Lines captured when the plot was paused
+    frozen: Option<Vec<(String, Vec<[f64; 2]>)>>,
+}
+
+impl Default for DataPlotUI {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DataPlotUI {
+    pub fn new() -> Self {
+        Self {
+            title: "Data".to_string(),
+            selected: Vec::new(),
+            window: DEFAULT_WINDOW,
+            frozen: None,
+        }
+    }
+
+    pub fn with_channels(channels: &[&str]) -> Self {
+        Self {
+            selected: channels.iter().map(|name| name.to_string()).collect(),
+            ..Self::new()
+        }
+    }
+
+    pub fn with_title(self, title: &str) -> Self {
+        Self { title: title.to_string(), ..self }
+    }
+
+    pub fn with_window(self, window: f32) -> Self {
+        Self { window, ..self }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.frozen.is_some()
+    }
+
+    fn collect_lines(&self, data_collector: &DataCollector) -> Vec<(String, Vec<[f64; 2]>)> {
+        let start = data_collector.time() - self.window;
+        self.selected.iter().filter_map(|name| {
+            let channel = data_collector.channel(name)?;
+            let count = channel.since(start).count();
+            let stride = (count / MAX_PLOTTED_POINTS).max(1);
+            let points = channel.since(start)
+                .step_by(stride)
+                .map(|sample| [sample.time as f64, sample.value as f64])
+                .collect();
+            Some((name.clone(), points))
+        }).collect()
+    }
+}
+
+impl EguiUI for DataPlotUI {
+    fn ui(&mut self, ctx: &egui::Context, _renderer: &Renderer, data_collector: &DataCollector) {
+        egui::Window::new(self.title.clone()).default_size([420.0, 300.0]).show(ctx, |ui| {
+            // Controls
+            ui.horizontal(|ui| {
+                let label = if self.is_paused() { "Resume" } else { "Pause" };
+                if ui.button(label).clicked() {
+                    self.frozen = match self.frozen {
+                        Some(_) => None,
+                        None => Some(self.collect_lines(data_collector)),
+                    };
+                }
+
+                ui.label("Window:");
+                ui.add(egui::DragValue::new(&mut self.window).speed(0.1).clamp_range(0.1..=3600.0).suffix(" s"));
+            });
+
+            // Channel picker
+            ui.collapsing("Channels", |ui| {
+                ui.horizontal_wrapped(|ui| {
+                    for name in data_collector.channel_names() {
+                        let mut checked = self.selected.iter().any(|selected| selected == name);
+                        if ui.checkbox(&mut checked, name).changed() {
+                            if checked {
+                                self.selected.push(name.to_string());
+                            } else {
+                                self.selected.retain(|selected| selected != name);
+                            }
+                        }
+                    }
+                });
+            });
+
+            ui.separator();
+
+            // Plot
+            let lines = match &self.frozen {
+                Some(lines) => lines.clone(),
+                None => self.collect_lines(data_collector),
+            };
+
+            let mut plot = Plot::new(("data_plot", &self.title))
+                .legend(Legend::default())
+                .allow_drag(self.is_paused())
+                .allow_zoom(self.is_paused())
+                .allow_scroll(self.is_paused());
+            if !self.is_paused() {
+                let end = data_collector.time();
+                plot = plot.include_x((end - self.window).max(0.0)).include_x(end);
+            }
+
+            plot.show(ui, |plot_ui| {
+                for (name, points) in lines {
+                    plot_ui.line(Line::new(PlotPoints::new(points)).name(name));
+                }
+            });
+        });
+    }
+}
@@ -1,3 +1,5 @@
 mod basic_data;
+mod data_plot;
 
-pub use basic_data::BasicDataUI;
\ No newline at end of file
+pub use basic_data::BasicDataUI;
+pub use data_plot::DataPlotUI;
\ No newline at end of file
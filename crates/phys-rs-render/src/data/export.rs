@@ -0,0 +1,359 @@
+use std::{io::{self, Write, BufWriter}, fs::File, path::Path, fmt};
+
+use super::DataCollector;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    // One column per channel, one row per recorded time
+    Csv,
+    // One JSON object per recorded time
+    JsonLines,
+}
+
+// ====< ROWS >====
+// Values of all channels recorded at a single point in time
+struct Row {
+    time: f32,
+    frame: u128,
+    values: Vec<Option<f32>>,
+}
+
+fn write_header(writer: &mut impl Write, format: ExportFormat, names: &[&str]) -> io::Result<()> {
+    match format {
+        ExportFormat::Csv => {
+            write!(writer, "time,frame")?;
+            for name in names {
+                write!(writer, ",{}", csv_escape(name))?;
+            }
+            writeln!(writer)
+        }
+        ExportFormat::JsonLines => Ok(()),
+    }
+}
+
+fn write_row(writer: &mut impl Write, format: ExportFormat, names: &[&str], row: &Row) -> io::Result<()> {
+    match format {
+        ExportFormat::Csv => {
+            write!(writer, "{},{}", row.time, row.frame)?;
+            for value in row.values.iter() {
+                match value {
+                    Some(value) => write!(writer, ",{}", value)?,
+                    None => write!(writer, ",")?,
+                }
+            }
+            writeln!(writer)
+        }
+        ExportFormat::JsonLines => {
+            write!(writer, "{{\"time\":{},\"frame\":{}", json_number(row.time), row.frame)?;
+            for (name, value) in names.iter().zip(row.values.iter()) {
+                if let Some(value) = value {
+                    write!(writer, ",{}:{}", json_string(name), json_number(*value))?;
+                }
+            }
+            writeln!(writer, "}}")
+        }
+    }
+}
+
+fn csv_escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+// JSON has no NaN or infinity
+fn json_number(value: f32) -> String {
+    if value.is_finite() {
+        value.to_string()
+    } else {
+        "null".to_string()
+    }
+}
+
+// ====< EXPORT >====
+impl DataCollector {
+    // Merges all channels into rows aligned on time. Missing values are `None`.
+    fn rows(&self) -> Vec<Row> {
+        let mut samples = Vec::new();
+        for (index, channel) in self.channels.iter().enumerate() {
+            for sample in channel.samples.iter() {
+                samples.push((index, sample));
+            }
+        }
+        samples.sort_by(|a, b| a.1.time.total_cmp(&b.1.time));
+
+        let mut rows: Vec<Row> = Vec::new();
+        for (index, sample) in samples {
+            let same_time = rows.last().map(|row| row.time == sample.time).unwrap_or(false);
+            if !same_time {
+                rows.push(Row {
+                    time: sample.time,
+                    frame: sample.frame,
+                    values: vec![None; self.channels.len()],
+                });
+            }
+            rows.last_mut().unwrap().values[index] = Some(sample.value);
+        }
+        rows
+    }
+
+    pub fn export(&self, writer: &mut impl Write, format: ExportFormat) -> io::Result<()> {
+        let names: Vec<&str> = self.channel_names().collect();
+
+        write_header(writer, format, &names)?;
+        for row in self.rows() {
+            write_row(writer, format, &names, &row)?;
+        }
+        writer.flush()
+    }
+
+    pub fn export_to_file(&self, path: impl AsRef<Path>, format: ExportFormat) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.export(&mut writer, format)
+    }
+
+    pub fn export_to_string(&self, format: ExportFormat) -> String {
+        let mut buffer = Vec::new();
+        self.export(&mut buffer, format).expect("writing to a Vec never fails");
+        String::from_utf8(buffer).expect("export is valid utf-8")
+    }
+
+    // ====< STREAMING >====
+    /// Writes one row per update step to `writer` while the simulation runs.
+    /// CSV columns are fixed after the first step, channels first recorded later are left out
+    /// and reported by `finish_stream`.
+    pub fn stream_to(&mut self, writer: impl Write + 'static, format: ExportFormat) {
+        self.stream = Some(DataStream {
+            writer: BufWriter::new(Box::new(writer)),
+            format,
+            columns: None,
+            pending: Vec::new(),
+            dropped: Vec::new(),
+            error: None,
+        });
+    }
+
+    pub fn stream_to_file(&mut self, path: impl AsRef<Path>, format: ExportFormat) -> io::Result<()> {
+        self.stream_to(File::create(path)?, format);
+        Ok(())
+    }
+
+    /// Flushes and closes the stream, returning the first error that happened while streaming.
+    /// Fails with `InvalidData` when CSV columns had to be left out, the other columns are still written.
+    pub fn finish_stream(&mut self) -> io::Result<()> {
+        match self.stream.take() {
+            Some(mut stream) => stream.finish(),
+            None => Ok(()),
+        }
+    }
+
+    pub fn is_streaming(&self) -> bool {
+        self.stream.is_some()
+    }
+}
+
+// ====< DATA STREAM >====
+pub(super) struct DataStream {
+    writer: BufWriter<Box<dyn Write>>,
+    format: ExportFormat,
+
+    // Channel indices written as CSV columns, set on the first row
+    columns: Option<Vec<usize>>,
+    // Channel, time and value of every sample recorded during the current step
+    pub(super) pending: Vec<(usize, f32, f32)>,
+    // Channels that had no CSV column when they were first recorded
+    dropped: Vec<String>,
+
+    error: Option<io::Error>,
+}
+
+impl DataStream {
+    // One row per distinct sample time, like `DataCollector::export`
+    pub(super) fn write_step(&mut self, frame: u128, names: &[&str]) {
+        if self.pending.is_empty() {
+            return;
+        }
+        if self.error.is_some() {
+            self.pending.clear();
+            return;
+        }
+
+        let columns: Vec<usize> = match (&self.columns, self.format) {
+            (Some(columns), _) => columns.clone(),
+            (None, ExportFormat::Csv) => {
+                let columns: Vec<usize> = (0..names.len()).collect();
+                if let Err(err) = write_header(&mut self.writer, self.format, names) {
+                    self.error = Some(err);
+                }
+                self.columns = Some(columns.clone());
+                columns
+            }
+            // Every known channel is a column, JSON lines simply skip the missing ones
+            (None, ExportFormat::JsonLines) => (0..names.len()).collect(),
+        };
+
+        // Sub-steps recorded with `record_at` get rows of their own
+        self.pending.sort_by(|a, b| a.1.total_cmp(&b.1));
+        let mut rows: Vec<Row> = Vec::new();
+        for (index, time, value) in self.pending.drain(..) {
+            let column = match columns.iter().position(|c| *c == index) {
+                Some(column) => column,
+                None => {
+                    if !self.dropped.iter().any(|name| name == names[index]) {
+                        self.dropped.push(names[index].to_string());
+                    }
+                    continue;
+                }
+            };
+            if rows.last().map(|row| row.time != time).unwrap_or(true) {
+                rows.push(Row {
+                    time,
+                    frame,
+                    values: vec![None; columns.len()],
+                });
+            }
+            rows.last_mut().unwrap().values[column] = Some(value);
+        }
+
+        let names: Vec<&str> = columns.iter().map(|index| names[*index]).collect();
+        for row in rows.iter() {
+            if let Err(err) = write_row(&mut self.writer, self.format, &names, row) {
+                self.error = Some(err);
+                return;
+            }
+        }
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        if let Some(err) = self.error.take() {
+            return Err(err);
+        }
+        self.writer.flush()?;
+        if !self.dropped.is_empty() {
+            let message = format!("channels recorded after the CSV header was written were left out: {}", self.dropped.join(", "));
+            return Err(io::Error::new(io::ErrorKind::InvalidData, message));
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for DataStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DataStream")
+            .field("format", &self.format)
+            .field("columns", &self.columns)
+            .field("dropped", &self.dropped)
+            .field("error", &self.error)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{rc::Rc, cell::RefCell};
+
+    use super::*;
+
+    // Lets the test read what the stream wrote
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl SharedBuffer {
+        fn contents(&self) -> String {
+            String::from_utf8(self.0.borrow().clone()).unwrap()
+        }
+    }
+
+    fn step(data_collector: &mut DataCollector, time: f32, values: &[(&str, f32)]) {
+        data_collector.begin_step(time, 0);
+        for (name, value) in values {
+            data_collector.record(name, *value);
+        }
+        data_collector.end_step();
+    }
+
+    #[test]
+    fn csv_stream_reports_late_channels() {
+        let buffer = SharedBuffer::default();
+        let mut data_collector = DataCollector::new();
+        data_collector.stream_to(buffer.clone(), ExportFormat::Csv);
+
+        step(&mut data_collector, 0.5, &[("x", 1.0)]);
+        step(&mut data_collector, 1.0, &[("x", 2.0), ("y", 3.0)]);
+        step(&mut data_collector, 1.5, &[("x", 4.0), ("y", 5.0)]);
+
+        let err = data_collector.finish_stream().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains('y'));
+        // The known columns are still complete
+        assert_eq!(buffer.contents(), "time,frame,x\n0.5,0,1\n1,0,2\n1.5,0,4\n");
+        assert!(!data_collector.is_streaming());
+    }
+
+    #[test]
+    fn finished_stream_writes_every_row() {
+        let buffer = SharedBuffer::default();
+        let mut data_collector = DataCollector::new();
+        data_collector.stream_to(buffer.clone(), ExportFormat::JsonLines);
+
+        step(&mut data_collector, 0.5, &[("x", 1.0)]);
+        step(&mut data_collector, 1.0, &[("x", 2.0), ("y", 3.0)]);
+
+        data_collector.finish_stream().unwrap();
+        assert_eq!(buffer.contents(), "{\"time\":0.5,\"frame\":0,\"x\":1}\n{\"time\":1,\"frame\":0,\"x\":2,\"y\":3}\n");
+    }
+
+    #[test]
+    fn stream_matches_export_with_sub_steps() {
+        for format in [ExportFormat::Csv, ExportFormat::JsonLines] {
+            let buffer = SharedBuffer::default();
+            let mut data_collector = DataCollector::new();
+            data_collector.stream_to(buffer.clone(), format);
+
+            for step in 1..=4 {
+                let time = step as f32 * 0.5;
+                data_collector.begin_step(time, step);
+                // Adaptive sub-steps inside the step, the last one on the step time
+                for (i, fraction) in [0.2, 0.7, 1.0].iter().enumerate() {
+                    data_collector.record_at("solver.dt", time - 0.5 + 0.5 * fraction, i as f32);
+                }
+                data_collector.record("x", step as f32);
+                data_collector.end_step();
+            }
+
+            data_collector.finish_stream().unwrap();
+            assert_eq!(buffer.contents(), data_collector.export_to_string(format));
+            assert_eq!(buffer.contents().lines().count(), 12 + usize::from(format == ExportFormat::Csv));
+        }
+    }
+}
@@ -0,0 +1,160 @@
+use std::collections::{HashMap, VecDeque};
+
+mod export;
+
+pub use export::ExportFormat;
+use export::DataStream;
+
+// ====< SAMPLE >====
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sample {
+    pub time: f32,
+    pub frame: u128,
+    pub value: f32,
+}
+
+// ====< CHANNEL >====
+#[derive(Debug, Clone)]
+pub struct Channel {
+    pub name: String,
+    pub samples: VecDeque<Sample>,
+}
+
+impl Channel {
+    pub fn new(name: String) -> Self {
+        Self {
+            name,
+            samples: VecDeque::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    pub fn last(&self) -> Option<&Sample> {
+        self.samples.back()
+    }
+
+    // Samples with time >= `time`
+    pub fn since(&self, time: f32) -> impl Iterator<Item = &Sample> {
+        let start = self.samples.partition_point(|s| s.time < time);
+        self.samples.range(start..)
+    }
+}
+
+// ====< DATA COLLECTOR >====
+/// Named time-series recorder handed to `PhysRenderable::update`.
+/// Every recorded value is stamped with the simulated time and frame of the current step.
+#[derive(Debug, Default)]
+pub struct DataCollector {
+    channels: Vec<Channel>,
+    indices: HashMap<String, usize>,
+
+    time: f32,
+    frame: u128,
+
+    // Oldest samples are dropped once a channel grows past this
+    pub max_samples: Option<usize>,
+
+    stream: Option<DataStream>,
+}
+
+impl DataCollector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_max_samples(max_samples: usize) -> Self {
+        Self {
+            max_samples: Some(max_samples),
+            ..Default::default()
+        }
+    }
+
+    /// Sets the time and frame that following `record` calls are stamped with.
+    /// Called by the scene after every physics step, with the time at the end of that step.
+    pub fn begin_step(&mut self, time: f32, frame: u128) {
+        self.time = time;
+        self.frame = frame;
+    }
+
+    /// Writes the values recorded since `begin_step` to the stream, if there is one.
+    pub fn end_step(&mut self) {
+        if let Some(stream) = self.stream.as_mut() {
+            let names: Vec<&str> = self.channels.iter().map(|channel| channel.name.as_str()).collect();
+            stream.write_step(self.frame, &names);
+        }
+    }
+
+    pub fn record(&mut self, name: &str, value: f32) {
+        self.record_at(name, self.time, value);
+    }
+
+    /// Records with a custom time stamp, e.g. for sub-steps of an adaptive solver.
+    /// Times should not go backwards within a channel.
+    pub fn record_at(&mut self, name: &str, time: f32, value: f32) {
+        let index = match self.indices.get(name) {
+            Some(index) => *index,
+            None => {
+                self.channels.push(Channel::new(name.to_string()));
+                self.indices.insert(name.to_string(), self.channels.len() - 1);
+                self.channels.len() - 1
+            }
+        };
+
+        if let Some(stream) = self.stream.as_mut() {
+            stream.pending.push((index, time, value));
+        }
+
+        let channel = &mut self.channels[index];
+        channel.samples.push_back(Sample {
+            time,
+            frame: self.frame,
+            value,
+        });
+
+        if let Some(max_samples) = self.max_samples {
+            while channel.samples.len() > max_samples {
+                channel.samples.pop_front();
+            }
+        }
+    }
+
+    // ====< DATA >====
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    pub fn frame(&self) -> u128 {
+        self.frame
+    }
+
+    pub fn channel(&self, name: &str) -> Option<&Channel> {
+        self.indices.get(name).map(|index| &self.channels[*index])
+    }
+
+    // In the order they were first recorded
+    pub fn channels(&self) -> impl Iterator<Item = &Channel> {
+        self.channels.iter()
+    }
+
+    pub fn channel_names(&self) -> impl Iterator<Item = &str> {
+        self.channels.iter().map(|channel| channel.name.as_str())
+    }
+
+    pub fn last_value(&self, name: &str) -> Option<f32> {
+        self.channel(name).and_then(|channel| channel.last()).map(|sample| sample.value)
+    }
+
+    // Drops all samples but keeps the channels, so streamed CSV columns stay valid
+    pub fn clear(&mut self) {
+        for channel in self.channels.iter_mut() {
+            channel.samples.clear();
+        }
+    }
+}
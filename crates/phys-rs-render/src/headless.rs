@@ -0,0 +1,125 @@
+use crate::{Scene, DataCollector, DEFAULT_TIMESTEP};
+
+// ====< HEADLESS APP >====
+/// Steps a `Scene` without a window, surface or GPU adapter.
+/// Only `PhysRenderable::update` is called, `render` never is.
+pub struct HeadlessApp {
+    pub scene: Scene,
+    pub data_collector: DataCollector,
+    pub timestep: f32,
+
+    pub frame: u128,
+    pub avg_update_time: f32,
+}
+
+impl Default for HeadlessApp {
+    fn default() -> Self {
+        Self::new(DEFAULT_TIMESTEP)
+    }
+}
+
+impl HeadlessApp {
+    pub fn new(timestep: f32) -> Self {
+        Self {
+            scene: Scene::default(),
+            data_collector: DataCollector::new(),
+            timestep,
+            frame: 0,
+            avg_update_time: 0.0,
+        }
+    }
+
+    pub fn set_scene(&mut self, scene: Scene) {
+        self.scene = scene;
+    }
+
+    // Every step counts as one frame
+    pub fn step(&mut self) {
+        self.scene.update(self.timestep, self.frame, &mut self.data_collector);
+        self.frame += 1;
+    }
+
+    pub fn run_steps(&mut self, steps: u64) {
+        if steps == 0 {
+            return;
+        }
+
+        let update_start = std::time::Instant::now();
+        for _ in 0..steps {
+            self.step();
+        }
+        self.avg_update_time = update_start.elapsed().as_secs_f32() / steps as f32;
+    }
+
+    /// Runs as many whole steps as needed to cover `duration` seconds of simulated time.
+    pub fn run_for(&mut self, duration: f32) {
+        // Small epsilon so that e.g. 1.0 / (1.0 / 60.0) does not round up to 61 steps
+        let steps = (duration / self.timestep - 1e-4).ceil().max(0.0) as u64;
+        self.run_steps(steps);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{math::Vector2, physics::{RigidBody, Shape, Spring, UniformGravity}, components::physics::Wall};
+
+    #[test]
+    fn run_for_covers_whole_steps() {
+        let mut app = HeadlessApp::new(1.0 / 60.0);
+        app.run_for(1.0);
+        assert_eq!(app.frame, 60);
+
+        app.run_for(0.5);
+        assert_eq!(app.frame, 90);
+
+        // A partial step still runs
+        app.run_for(0.001);
+        assert_eq!(app.frame, 91);
+
+        app.run_for(0.0);
+        assert_eq!(app.frame, 91);
+    }
+
+    #[test]
+    fn time_advances_by_timestep() {
+        let mut app = HeadlessApp::new(1.0 / 120.0);
+        app.run_steps(240);
+        assert_eq!(app.frame, 240);
+        assert!((app.scene.time - 240.0 * app.timestep).abs() < 1e-4);
+
+        app.run_for(0.25);
+        assert_eq!(app.frame, 270);
+        assert!((app.scene.time - 270.0 * app.timestep).abs() < 1e-4);
+    }
+
+    #[test]
+    fn samples_are_stamped_after_the_step() {
+        let mut app = HeadlessApp::new(0.01);
+        let body = app.scene.add_body(RigidBody::create(Shape::circle(1.0), 1.0, Vector2::new(10.0, 0.0)));
+        let spring = Spring::create(&app.scene.world, body, Vector2::new(10.0, 0.0), None, Vector2::zero(), 1.0, 5.0).with_name("s");
+        app.scene.world.add_spring(spring);
+        app.run_steps(3);
+
+        let times: Vec<f32> = app.data_collector.channel("s.tension").unwrap().samples.iter().map(|sample| sample.time).collect();
+        for (i, time) in times.iter().enumerate() {
+            assert!((time - (i + 1) as f32 * 0.01).abs() < 1e-6);
+        }
+        assert_eq!(times.len(), 3);
+    }
+
+    #[test]
+    fn falling_body_rests_on_a_wall() {
+        let mut app = HeadlessApp::new(1.0 / 120.0);
+        app.scene.add_wall(Wall::create(Vector2::new(-100.0, 100.0), Vector2::new(100.0, 100.0), Vector2::new(0.0, -1.0)));
+        app.scene.world.add_field(UniformGravity::new(Vector2::new(0.0, 500.0)));
+        let body = app.scene.add_body(RigidBody::create(Shape::circle(10.0), 1.0, Vector2::zero()));
+        app.run_for(3.0);
+
+        // Surface is at y = 100, so the centre rests one radius above it
+        let body = app.scene.world.body(body);
+        assert!((body.position.y - 90.0).abs() < 1.0, "{}", body.position.y);
+        assert!(body.position.x.abs() < 1e-3);
+        assert!(body.velocity.length() < 1.0, "{:?}", body.velocity);
+    }
+}
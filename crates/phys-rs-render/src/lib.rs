@@ -1,31 +1,62 @@
-use std::thread;
-
+use vec2::Vector2;
+use physics::{PhysicsWorld, RigidBody, BodyHandle, ContactSolver};
+use components::physics::Wall;
 use color::{Color, StandardColorPalette};
-use winit::{window::{WindowBuilder}, dpi::PhysicalSize, event_loop::{EventLoop, ControlFlow}, event::{Event, WindowEvent}, platform::run_return::EventLoopExtRunReturn};
+use winit::{window::{WindowBuilder}, dpi::PhysicalSize, event_loop::{EventLoop, ControlFlow}, event::{Event, WindowEvent, MouseButton, ElementState, MouseScrollDelta}};
 
 mod renderer;
 mod color;
 mod vec2;
+mod camera;
+mod units;
 mod pipeline;
+mod headless;
+mod data;
 
 pub mod components;
+pub mod physics;
 
-pub use renderer::{Renderer, Brush};
+pub use renderer::{Renderer, Brush, CollisionCounters};
+pub use headless::HeadlessApp;
+pub use camera::Camera2D;
+pub use units::Units;
+pub use pipeline::elements::Grid;
+pub use data::{DataCollector, Channel, Sample, ExportFormat};
 
 // ====< EXPORTS >====
 pub use color::StandardColorPalette as ColorPalette;
 pub mod math {
     pub use crate::vec2::Vector2;
 }
+pub mod text {
+    pub use crate::pipeline::elements::{Text, TextAnchor};
+    pub use wgpu_glyph::{FontId, ab_glyph::InvalidFont};
+}
 
 // ====< PHYS APP >====
+pub(crate) const DEFAULT_TIMESTEP: f32 = 1.0 / 120.0;
+const DEFAULT_MAX_STEPS_PER_FRAME: u32 = 32;
+// Samples kept per channel by the interactive app, about 8 minutes at the default timestep.
+// Stream to a file to keep everything
+const DEFAULT_MAX_SAMPLES: usize = 60_000;
+// Zoom factor per line of mouse wheel scroll
+const WHEEL_ZOOM_STEP: f32 = 1.1;
+const PIXELS_PER_WHEEL_LINE: f32 = 40.0;
+
 pub struct PhysApp {
     pub window_settings: WindowSettings,
     pub event_loop: EventLoop<()>,
     pub renderer: Renderer,
     pub scene: Scene,
-    pub updates_per_frame: u32,
-    pub avg_update_time: f32,
+    pub data_collector: DataCollector,
+
+    // Fixed physics timestep, independent of the frame rate
+    pub timestep: f32,
+    // Upper bound of updates run in a single frame, the remaining time is dropped
+    pub max_steps_per_frame: u32,
+
+    // Pan the scene camera by dragging with the left mouse button and zoom with the wheel
+    pub camera_controls: bool,
 }
 
 impl PhysApp {
@@ -44,8 +75,10 @@ impl PhysApp {
             event_loop,
             renderer,
             scene,
-            updates_per_frame: 1,
-            avg_update_time: 0.0,
+            data_collector: DataCollector::with_max_samples(DEFAULT_MAX_SAMPLES),
+            timestep: DEFAULT_TIMESTEP,
+            max_steps_per_frame: DEFAULT_MAX_STEPS_PER_FRAME,
+            camera_controls: true,
         }
     }
 
@@ -56,41 +89,93 @@ impl PhysApp {
     pub fn run(mut self) -> ! {
         let start_time = std::time::Instant::now();
         let mut last_frame = std::time::Instant::now();
+        let mut accumulator: f32 = 0.0;
         let mut frame: u128 = 0;
+        let mut cursor = Vector2::zero();
+        let mut dragging = false;
         self.event_loop.run(move |event, _, control_flow| {
             self.renderer.handle_event(&event);
+            // Egui windows get the mouse first
+            let ui_wants_pointer = self.renderer.platform.context().wants_pointer_input();
             match event {
                 Event::WindowEvent { event, .. } => match event {
-                    WindowEvent::CloseRequested => *control_flow = ControlFlow::Exit,
+                    WindowEvent::CloseRequested => {
+                        // Writes the last buffered rows
+                        if let Err(err) = self.data_collector.finish_stream() {
+                            eprintln!("data stream: {}", err);
+                        }
+                        *control_flow = ControlFlow::Exit;
+                    }
                     WindowEvent::Resized(physical_size) => {
                         self.renderer.resize(physical_size);
                     }
+
+                    // camera controls
+                    WindowEvent::MouseInput { button: MouseButton::Left, state, .. } => {
+                        dragging = self.camera_controls && state == ElementState::Pressed && !ui_wants_pointer;
+                    }
+                    WindowEvent::CursorMoved { position, .. } => {
+                        let position = Vector2::new(position.x as f32, position.y as f32);
+                        if dragging {
+                            self.scene.camera.pan(position - cursor);
+                        }
+                        cursor = position;
+                    }
+                    WindowEvent::MouseWheel { delta, .. } if self.camera_controls && !ui_wants_pointer => {
+                        let lines = match delta {
+                            MouseScrollDelta::LineDelta(_, y) => y,
+                            MouseScrollDelta::PixelDelta(position) => position.y as f32 / PIXELS_PER_WHEEL_LINE,
+                        };
+                        let resolution = self.renderer.resolution();
+                        self.scene.camera.zoom_at(cursor, WHEEL_ZOOM_STEP.powf(lines), resolution);
+                    }
                     _ => (),
                 },
                 Event::RedrawRequested(_) => {
-                    self.renderer.render_begin(&mut self.scene, start_time);
-                    // draw
-                    let dt = last_frame.elapsed().as_secs_f32();
+                    let now = std::time::Instant::now();
+                    let dt = (now - last_frame).as_secs_f32();
+                    last_frame = now;
                     self.renderer.ldt = dt;
 
+                    // update (fixed timestep)
+                    // Never accumulate more than we are allowed to simulate in one frame,
+                    // otherwise a slow frame makes the next one even slower.
+                    accumulator += dt.min(self.timestep * self.max_steps_per_frame as f32);
+                    let update_start = std::time::Instant::now();
+                    let mut steps = 0;
+                    while accumulator >= self.timestep && steps < self.max_steps_per_frame {
+                        self.scene.update(self.timestep, frame, &mut self.data_collector);
+                        accumulator -= self.timestep;
+                        steps += 1;
+                    }
+                    if steps > 0 {
+                        self.renderer.avg_update_time = update_start.elapsed().as_secs_f32() / steps as f32;
+                        let stats = self.scene.world.broad_phase_stats();
+                        self.renderer.collision_counters = CollisionCounters {
+                            broad_phase: stats.name,
+                            bodies: stats.bodies,
+                            candidate_pairs: stats.candidates,
+                            all_pairs: stats.all_pairs(),
+                            collisions: stats.collisions,
+                            broad_phase_time: stats.time,
+                        };
+                    }
+
+                    // draw
+                    // How far we are between the last and the next update, in range [0, 1)
+                    let alpha = (accumulator / self.timestep).clamp(0.0, 1.0);
+
+                    self.renderer.render_begin(&mut self.scene, start_time);
                     let mut brush = self.renderer.brush.take().unwrap();
+                    self.scene.world.render(&mut brush, alpha);
                     for object in self.scene.objects.iter_mut() {
-                        object.render(&mut brush, &mut self.renderer, dt, frame);
+                        // Layers set by one object do not leak into the next one
+                        brush.set_layer(0);
+                        object.render(&mut brush, &mut self.renderer, dt, alpha, frame);
                     }
                     self.renderer.brush = Some(brush);
+                    self.renderer.render_end(&mut self.scene, &self.data_collector);
 
-                    // update
-                    let dt = dt / self.updates_per_frame as f32;
-                    let update_start = std::time::Instant::now();
-                    for _ in 0..self.updates_per_frame {
-                        for object in self.scene.objects.iter_mut() {
-                            object.update(dt, frame, None);
-                        }
-                    }
-                    self.renderer.avg_update_time = update_start.elapsed().as_secs_f32() / self.updates_per_frame as f32;
-
-                    self.renderer.render_end(&mut self.scene);
-                    last_frame = std::time::Instant::now();
                     frame += 1;
                 }
                 Event::MainEventsCleared => {
@@ -115,52 +200,106 @@ impl WindowSettings {
 }
 
 // ====< SCENE >====
-// Scene, Brush, dt, frame count
-type UpdateFn = Box<dyn FnMut(&mut Scene, &mut Brush, f32, u128)>;
 pub struct Scene {
     pub ui: Option<Box<dyn EguiUI>>,
     pub background_color: Color,
+    pub camera: Camera2D,
+    pub units: Units,
+    // Spacing is in world units
+    pub grid: Option<Grid>,
 
     pub objects: Vec<Box<dyn PhysRenderable>>,
+    pub world: PhysicsWorld,
 
-    // pub update: Option<UpdateFn>,
-
+    // Simulated time in seconds
+    pub time: f32,
 }
 impl Default for Scene {
     fn default() -> Self {
         Self {
             ui: None,
             background_color: StandardColorPalette::BACKGROUND,
+            camera: Camera2D::default(),
+            units: Units::default(),
+            grid: Some(Grid::fullscreen(StandardColorPalette::GRID, 30.0, 1.0, 5)),
             objects: Vec::new(),
-            // update: None,
+            world: PhysicsWorld::new(),
+            time: 0.0,
         }
     }
 }
 impl Scene {
     pub fn new() -> Self {
-        Self {
-            // update,
-            ..Default::default()
+        Self::default()
+    }
+
+    // Also rescales what is sized in world units, the grid spacing and the contact solver tolerances,
+    // so they stay the same size on screen
+    pub fn set_units(&mut self, units: Units) {
+        if let Some(grid) = self.grid.as_mut() {
+            grid.spacing = units.length_from_pixels(self.units.length_to_pixels(grid.spacing));
         }
+        let solver = ContactSolver::for_units(&units);
+        self.world.contact_solver.slop = solver.slop;
+        self.world.contact_solver.restitution_threshold = solver.restitution_threshold;
+        self.units = units;
     }
 
     pub fn add_object(&mut self, object: Box<dyn PhysRenderable>) {
         self.objects.push(object);
     }
-}
 
-// ====< DATA COLLECTOR >====
-pub struct DataCollector {
+    pub fn add_body(&mut self, body: RigidBody) -> BodyHandle {
+        self.world.add_body(body)
+    }
+
+    // Adds the wall collider to the physics world and the wall itself to the objects that get drawn
+    pub fn add_wall(&mut self, wall: Wall) -> BodyHandle {
+        let handle = self.world.add_body(wall.body());
+        self.add_object(Box::new(wall));
+        handle
+    }
+
+    /// Advances every object and the physics world by a single update step. Does not touch the GPU.
+    pub fn update(&mut self, dt: f32, frame: u128, data_collector: &mut DataCollector) {
+        for object in self.objects.iter_mut() {
+            object.apply_forces(&mut self.world, dt);
+        }
+        self.world.step(dt);
+        self.time += dt;
+
+        // Everything recorded from here on describes the state at the end of the step
+        data_collector.begin_step(self.time, frame);
+        self.world.record(data_collector);
+        for object in self.objects.iter_mut() {
+            object.update(dt, frame, Some(data_collector));
+        }
+        data_collector.end_step();
+    }
 }
 
 // ====< TRAITS >====
 pub trait PhysRenderable {
-    fn render(&self, brush: &mut Brush, renderer: &mut Renderer, dt: f32, frame: u128);
+    // `alpha` is the interpolation factor between the previous and the current update
+    fn render(&self, brush: &mut Brush, renderer: &mut Renderer, dt: f32, alpha: f32, frame: u128);
     fn update(&mut self, dt: f32, frame: u128, data_collector: Option<&mut DataCollector>);
+
+    // Called every step before the physics world moves. The forces stay constant for the whole step,
+    // even for RK4 and Verlet, use a `Spring` or a `ForceField` for forces that depend on the state
+    fn apply_forces(&mut self, _world: &mut PhysicsWorld, _dt: f32) {}
 }
 
 pub trait EguiUI {
-    fn ui(&mut self, ctx: &egui::Context, renderer: &Renderer);
+    fn ui(&mut self, ctx: &egui::Context, renderer: &Renderer, data_collector: &DataCollector);
+}
+
+// Allows a scene to show more than one UI
+impl EguiUI for Vec<Box<dyn EguiUI>> {
+    fn ui(&mut self, ctx: &egui::Context, renderer: &Renderer, data_collector: &DataCollector) {
+        for ui in self.iter_mut() {
+            ui.ui(ctx, renderer, data_collector);
+        }
+    }
 }
 
 // ====< UTILS >====
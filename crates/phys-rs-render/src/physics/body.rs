@@ -0,0 +1,328 @@
+use crate::{Brush, math::Vector2, color::Color, ColorPalette};
+
+const OUTLINE_THICKNESS: f32 = 1.5; // pixels
+
+// ====< SHAPE >====
+#[derive(Debug, Clone, PartialEq)]
+pub enum Shape {
+    Circle { radius: f32 },
+    // Axis aligned when the body angle is 0
+    Box { size: Vector2 },
+    // Convex, counter-clockwise and centered on the centroid, see `Shape::polygon`
+    Polygon { vertices: Vec<Vector2> },
+}
+
+impl Shape {
+    pub fn circle(radius: f32) -> Self {
+        Shape::Circle { radius }
+    }
+
+    pub fn rectangle(width: f32, height: f32) -> Self {
+        Shape::Box { size: Vector2::new(width, height) }
+    }
+
+    // Moves the points so that the centroid is at the origin of the body
+    pub fn polygon(points: &[Vector2]) -> Self {
+        assert!(points.len() >= 3, "a polygon needs at least 3 points");
+        assert!(polygon_signed_area(points).abs() > f32::EPSILON, "polygon points are all on one line");
+        assert!(points.iter().zip(points.iter().cycle().skip(1)).all(|(a, b)| (*b - *a).length() > f32::EPSILON), "polygon has repeated points");
+        let centroid = polygon_centroid(points);
+        let mut vertices: Vec<Vector2> = points.iter().map(|point| *point - centroid).collect();
+        if polygon_signed_area(&vertices) < 0.0 {
+            vertices.reverse();
+        }
+        Shape::Polygon { vertices }
+    }
+
+    pub fn area(&self) -> f32 {
+        match self {
+            Shape::Circle { radius } => std::f32::consts::PI * radius * radius,
+            Shape::Box { size } => size.x * size.y,
+            Shape::Polygon { vertices } => polygon_signed_area(vertices).abs(),
+        }
+    }
+
+    // Moment of inertia around the centroid
+    pub fn inertia(&self, mass: f32) -> f32 {
+        match self {
+            Shape::Circle { radius } => 0.5 * mass * radius * radius,
+            Shape::Box { size } => mass * (size.x * size.x + size.y * size.y) / 12.0,
+            Shape::Polygon { vertices } => {
+                let (mut numerator, mut denominator) = (0.0, 0.0);
+                for (i, a) in vertices.iter().enumerate() {
+                    let b = vertices[(i + 1) % vertices.len()];
+                    let cross = a.cross(b).abs();
+                    numerator += cross * (a.dot(*a) + a.dot(b) + b.dot(b));
+                    denominator += cross;
+                }
+                if denominator == 0.0 { 0.0 } else { mass * numerator / (6.0 * denominator) }
+            }
+        }
+    }
+
+    // Corners in body space, empty for circles
+    pub fn vertices(&self) -> Vec<Vector2> {
+        match self {
+            Shape::Circle { .. } => Vec::new(),
+            Shape::Box { size } => {
+                let half = *size / 2.0;
+                vec![
+                    Vector2::new(-half.x, -half.y),
+                    Vector2::new(half.x, -half.y),
+                    Vector2::new(half.x, half.y),
+                    Vector2::new(-half.x, half.y),
+                ]
+            }
+            Shape::Polygon { vertices } => vertices.clone(),
+        }
+    }
+
+    // Radius of the smallest circle around the body origin that contains the shape
+    pub fn bounding_radius(&self) -> f32 {
+        match self {
+            Shape::Circle { radius } => *radius,
+            _ => self.vertices().iter().map(|vertex| vertex.length()).fold(0.0, f32::max),
+        }
+    }
+}
+
+fn polygon_signed_area(points: &[Vector2]) -> f32 {
+    let mut area = 0.0;
+    for (i, a) in points.iter().enumerate() {
+        area += a.cross(points[(i + 1) % points.len()]);
+    }
+    area / 2.0
+}
+
+fn polygon_centroid(points: &[Vector2]) -> Vector2 {
+    let area = polygon_signed_area(points);
+    let mut centroid = Vector2::zero();
+    for (i, a) in points.iter().enumerate() {
+        let b = points[(i + 1) % points.len()];
+        centroid += (*a + b) * a.cross(b);
+    }
+    centroid / (6.0 * area)
+}
+
+// ====< RIGID BODY >====
+#[derive(Debug, Clone)]
+pub struct RigidBody {
+    pub shape: Shape,
+    pub color: Color,
+    // Hidden bodies still collide, e.g. when a component draws them itself
+    pub visible: bool,
+
+    // Infinite (or zero) mass makes the body static
+    pub mass: f32,
+    pub inertia: f32,
+
+    pub position: Vector2,
+    pub velocity: Vector2,
+    // Counter-clockwise in world space, in radians
+    pub angle: f32,
+    pub angular_velocity: f32,
+
+    // Accumulated during a step, cleared after it
+    pub force: Vector2,
+    pub torque: f32,
+
+    // 0 is perfectly inelastic, 1 perfectly elastic. The bouncier body of a pair wins
+    pub restitution: f32,
+    // Coulomb friction coefficients, mixed with the other body by geometric mean
+    pub static_friction: f32,
+    pub kinetic_friction: f32,
+
+    // State before the last step, used to interpolate rendering
+    pub(crate) previous_position: Vector2,
+    pub(crate) previous_angle: f32,
+}
+
+impl RigidBody {
+    pub fn create(shape: Shape, mass: f32, position: Vector2) -> Self {
+        let inertia = shape.inertia(mass);
+        Self {
+            shape,
+            color: ColorPalette::BLUE,
+            visible: true,
+            mass,
+            inertia,
+            position,
+            velocity: Vector2::zero(),
+            angle: 0.0,
+            angular_velocity: 0.0,
+            force: Vector2::zero(),
+            torque: 0.0,
+            restitution: 0.0,
+            static_friction: 0.5,
+            kinetic_friction: 0.3,
+            previous_position: position,
+            previous_angle: 0.0,
+        }
+    }
+
+    // Infinite mass, never moves but everything collides with it
+    pub fn fixed(shape: Shape, position: Vector2) -> Self {
+        Self::create(shape, f32::INFINITY, position)
+    }
+
+    // Mass from the shape area
+    pub fn with_density(shape: Shape, density: f32, position: Vector2) -> Self {
+        let mass = shape.area() * density;
+        Self::create(shape, mass, position)
+    }
+
+    pub fn with_velocity(self, velocity: Vector2) -> Self {
+        Self { velocity, ..self }
+    }
+
+    pub fn with_angle(self, angle: f32) -> Self {
+        Self { angle, previous_angle: angle, ..self }
+    }
+
+    pub fn with_angular_velocity(self, angular_velocity: f32) -> Self {
+        Self { angular_velocity, ..self }
+    }
+
+    pub fn with_color(self, color: Color) -> Self {
+        Self { color, ..self }
+    }
+
+    pub fn hidden(self) -> Self {
+        Self { visible: false, ..self }
+    }
+
+    pub fn with_restitution(self, restitution: f32) -> Self {
+        Self { restitution, ..self }
+    }
+
+    pub fn with_friction(self, static_friction: f32, kinetic_friction: f32) -> Self {
+        Self { static_friction, kinetic_friction, ..self }
+    }
+
+    // Also updates the moment of inertia
+    pub fn set_mass(&mut self, mass: f32) {
+        self.mass = mass;
+        self.inertia = self.shape.inertia(mass);
+    }
+
+    pub fn inv_mass(&self) -> f32 {
+        if self.mass.is_finite() && self.mass > 0.0 { 1.0 / self.mass } else { 0.0 }
+    }
+
+    pub fn inv_inertia(&self) -> f32 {
+        if self.inertia.is_finite() && self.inertia > 0.0 { 1.0 / self.inertia } else { 0.0 }
+    }
+
+    pub fn is_static(&self) -> bool {
+        self.inv_mass() == 0.0
+    }
+
+    // ====< FORCES >====
+    pub fn apply_force(&mut self, force: Vector2) {
+        self.force += force;
+    }
+
+    // `point` is in world space
+    pub fn apply_force_at(&mut self, force: Vector2, point: Vector2) {
+        self.force += force;
+        self.torque += (point - self.position).cross(force);
+    }
+
+    pub fn apply_torque(&mut self, torque: f32) {
+        self.torque += torque;
+    }
+
+    // Instant change of momentum, `point` is in world space
+    pub fn apply_impulse(&mut self, impulse: Vector2, point: Vector2) {
+        self.velocity += impulse * self.inv_mass();
+        self.angular_velocity += (point - self.position).cross(impulse) * self.inv_inertia();
+    }
+
+    pub fn clear_forces(&mut self) {
+        self.force = Vector2::zero();
+        self.torque = 0.0;
+    }
+
+    // ====< SPACES >====
+    pub fn world_point(&self, local: Vector2) -> Vector2 {
+        self.position + local.rotate(self.angle)
+    }
+
+    pub fn local_point(&self, world: Vector2) -> Vector2 {
+        (world - self.position).rotate(-self.angle)
+    }
+
+    // Like `world_point`, between the previous and the current step
+    pub(crate) fn interpolated_point(&self, local: Vector2, alpha: f32) -> Vector2 {
+        let position = self.previous_position + (self.position - self.previous_position) * alpha;
+        let angle = self.previous_angle + (self.angle - self.previous_angle) * alpha;
+        position + local.rotate(angle)
+    }
+
+    // Velocity of a point attached to the body, in world space
+    pub fn velocity_at(&self, point: Vector2) -> Vector2 {
+        let r = point - self.position;
+        self.velocity + Vector2::new(-r.y, r.x) * self.angular_velocity
+    }
+
+    // Shape corners in world space, empty for circles
+    pub fn world_vertices(&self) -> Vec<Vector2> {
+        self.shape.vertices().into_iter().map(|vertex| self.world_point(vertex)).collect()
+    }
+
+    // `point` is in world space, the outline counts as inside
+    pub fn contains_point(&self, point: Vector2) -> bool {
+        let local = self.local_point(point);
+        match &self.shape {
+            Shape::Circle { radius } => local.length() <= *radius,
+            shape => {
+                let vertices = shape.vertices();
+                (0..vertices.len()).all(|i| (vertices[(i + 1) % vertices.len()] - vertices[i]).cross(local - vertices[i]) >= 0.0)
+            }
+        }
+    }
+
+    // ====< ENERGY >====
+    pub fn momentum(&self) -> Vector2 {
+        if self.is_static() {
+            return Vector2::zero();
+        }
+        self.velocity * self.mass
+    }
+
+    pub fn kinetic_energy(&self) -> f32 {
+        if self.is_static() {
+            return 0.0;
+        }
+        let v = self.velocity.length();
+        0.5 * self.mass * v * v + 0.5 * self.inertia * self.angular_velocity * self.angular_velocity
+    }
+
+    // ====< RENDER >====
+    // Draws the body `alpha` of the way between the previous and the current step
+    pub fn render(&self, brush: &mut Brush, alpha: f32) {
+        let position = self.previous_position + (self.position - self.previous_position) * alpha;
+        let angle = self.previous_angle + (self.angle - self.previous_angle) * alpha;
+        let outline = brush.px(OUTLINE_THICKNESS);
+
+        match &self.shape {
+            Shape::Circle { radius } => {
+                brush.draw_circle_filled(position, *radius, self.color);
+                brush.draw_circle(position, *radius, ColorPalette::BLACK, outline);
+                // Radius marker, so that rotation is visible
+                brush.draw_line(position, position + Vector2::from_angle(angle) * *radius, outline, ColorPalette::BLACK);
+            }
+            Shape::Box { size } => {
+                // Quads turn the other way around
+                brush._draw_quad_border_raw(position, *size, self.color, outline, ColorPalette::BLACK, -angle, 0.0);
+            }
+            Shape::Polygon { vertices } => {
+                let points: Vec<Vector2> = vertices.iter().map(|vertex| position + vertex.rotate(angle)).collect();
+                brush.draw_polygon_filled(&points, self.color);
+                for (i, a) in points.iter().enumerate() {
+                    brush.draw_line_rounded(*a, points[(i + 1) % points.len()], outline, ColorPalette::BLACK);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,371 @@
+use std::collections::HashMap;
+
+use crate::math::Vector2;
+
+use super::{RigidBody, Shape};
+
+// Bodies covering more cells than this are tested against everything instead, e.g. the floor
+const MAX_CELLS_PER_BODY: i64 = 64;
+// Automatic cell size, relative to the average body size
+const CELL_SIZE_FACTOR: f32 = 2.0;
+
+// ====< AABB >====
+/// Axis aligned bounding box in world space.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min: Vector2,
+    pub max: Vector2,
+}
+
+impl Aabb {
+    pub fn new(min: Vector2, max: Vector2) -> Self {
+        Self { min, max }
+    }
+
+    pub fn from_center(center: Vector2, half_size: Vector2) -> Self {
+        Self::new(center - half_size, center + half_size)
+    }
+
+    pub fn from_points(points: &[Vector2]) -> Self {
+        let mut aabb = Self::new(Vector2::new(f32::INFINITY, f32::INFINITY), Vector2::new(f32::NEG_INFINITY, f32::NEG_INFINITY));
+        for point in points {
+            aabb.min = Vector2::new(aabb.min.x.min(point.x), aabb.min.y.min(point.y));
+            aabb.max = Vector2::new(aabb.max.x.max(point.x), aabb.max.y.max(point.y));
+        }
+        aabb
+    }
+
+    pub fn from_body(body: &RigidBody) -> Self {
+        match body.shape {
+            Shape::Circle { radius } => Self::from_center(body.position, Vector2::new(radius, radius)),
+            _ => Self::from_points(&body.world_vertices()),
+        }
+    }
+
+    pub fn size(&self) -> Vector2 {
+        self.max - self.min
+    }
+
+    // Touching boxes overlap
+    pub fn overlaps(&self, other: &Aabb) -> bool {
+        self.min.x <= other.max.x && other.min.x <= self.max.x && self.min.y <= other.max.y && other.min.y <= self.max.y
+    }
+
+    pub fn contains(&self, point: Vector2) -> bool {
+        (self.min.x..=self.max.x).contains(&point.x) && (self.min.y..=self.max.y).contains(&point.y)
+    }
+}
+
+// ====< STATS >====
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct BroadPhaseStats {
+    pub name: &'static str,
+    pub bodies: usize,
+    // Pairs handed to the narrow phase
+    pub candidates: usize,
+    pub collisions: usize,
+    // Seconds spent finding the candidates
+    pub time: f32,
+}
+
+impl BroadPhaseStats {
+    // Pairs a brute force test would check
+    pub fn all_pairs(&self) -> usize {
+        self.bodies * self.bodies.saturating_sub(1) / 2
+    }
+}
+
+// ====< BROAD PHASE >====
+/// Finds pairs of bodies whose bounding boxes overlap, so the narrow phase only tests those.
+/// Bodies are referred to by their index in the slice given to `update`.
+pub trait BroadPhase {
+    fn name(&self) -> &'static str;
+
+    // Called once per step with the boxes of every body
+    fn update(&mut self, aabbs: &[Aabb]);
+
+    // Overlapping pairs, each one once with the smaller index first, sorted
+    fn pairs(&self) -> Vec<(usize, usize)>;
+
+    // Bodies whose box overlaps `region`, sorted
+    fn query_region(&self, region: Aabb) -> Vec<usize>;
+
+    fn query_point(&self, point: Vector2) -> Vec<usize> {
+        self.query_region(Aabb::new(point, point))
+    }
+}
+
+// ====< BRUTE FORCE >====
+/// Checks every pair, fine for a handful of bodies.
+#[derive(Debug, Clone, Default)]
+pub struct BruteForce {
+    aabbs: Vec<Aabb>,
+}
+
+impl BroadPhase for BruteForce {
+    fn name(&self) -> &'static str {
+        "Brute force"
+    }
+
+    fn update(&mut self, aabbs: &[Aabb]) {
+        self.aabbs = aabbs.to_vec();
+    }
+
+    fn pairs(&self) -> Vec<(usize, usize)> {
+        let mut pairs = Vec::new();
+        for i in 0..self.aabbs.len() {
+            for j in (i + 1)..self.aabbs.len() {
+                if self.aabbs[i].overlaps(&self.aabbs[j]) {
+                    pairs.push((i, j));
+                }
+            }
+        }
+        pairs
+    }
+
+    fn query_region(&self, region: Aabb) -> Vec<usize> {
+        (0..self.aabbs.len()).filter(|i| self.aabbs[*i].overlaps(&region)).collect()
+    }
+}
+
+// ====< SPATIAL HASH >====
+/// Uniform grid of cells, works best when the bodies are about the same size.
+#[derive(Debug, Clone, Default)]
+pub struct SpatialHash {
+    // `None` picks twice the average body size on every update
+    pub cell_size: Option<f32>,
+
+    size: f32,
+    aabbs: Vec<Aabb>,
+    cells: HashMap<(i32, i32), Vec<usize>>,
+    // Too big for the grid
+    large: Vec<usize>,
+}
+
+impl SpatialHash {
+    pub fn new(cell_size: f32) -> Self {
+        Self { cell_size: Some(cell_size), ..Default::default() }
+    }
+
+    // Cell size used by the last update
+    pub fn size(&self) -> f32 {
+        self.size
+    }
+
+    fn cell_range(&self, aabb: &Aabb) -> ((i32, i32), (i32, i32)) {
+        let cell = |value: f32| (value / self.size).floor() as i32;
+        ((cell(aabb.min.x), cell(aabb.min.y)), (cell(aabb.max.x), cell(aabb.max.y)))
+    }
+
+    fn cell_count(((x0, y0), (x1, y1)): ((i32, i32), (i32, i32))) -> i64 {
+        (x1 as i64 - x0 as i64 + 1) * (y1 as i64 - y0 as i64 + 1)
+    }
+}
+
+impl BroadPhase for SpatialHash {
+    fn name(&self) -> &'static str {
+        "Spatial hash"
+    }
+
+    fn update(&mut self, aabbs: &[Aabb]) {
+        self.aabbs = aabbs.to_vec();
+        self.cells.clear();
+        self.large.clear();
+
+        self.size = match self.cell_size {
+            Some(size) => size,
+            None if aabbs.is_empty() => 1.0,
+            None => {
+                let average = aabbs.iter().map(|aabb| aabb.size().x.max(aabb.size().y)).sum::<f32>() / aabbs.len() as f32;
+                average * CELL_SIZE_FACTOR
+            }
+        };
+        if !(self.size > 0.0 && self.size.is_finite()) {
+            self.size = 1.0;
+        }
+
+        for (i, aabb) in aabbs.iter().enumerate() {
+            let range = self.cell_range(aabb);
+            if Self::cell_count(range) > MAX_CELLS_PER_BODY {
+                self.large.push(i);
+                continue;
+            }
+
+            let ((x0, y0), (x1, y1)) = range;
+            for x in x0..=x1 {
+                for y in y0..=y1 {
+                    self.cells.entry((x, y)).or_default().push(i);
+                }
+            }
+        }
+    }
+
+    fn pairs(&self) -> Vec<(usize, usize)> {
+        let mut pairs = Vec::new();
+        for cell in self.cells.values() {
+            for (n, i) in cell.iter().enumerate() {
+                for j in cell[n + 1..].iter() {
+                    if self.aabbs[*i].overlaps(&self.aabbs[*j]) {
+                        pairs.push((*i.min(j), *i.max(j)));
+                    }
+                }
+            }
+        }
+
+        for i in self.large.iter() {
+            for j in 0..self.aabbs.len() {
+                if *i != j && (!self.large.contains(&j) || *i < j) && self.aabbs[*i].overlaps(&self.aabbs[j]) {
+                    pairs.push((*i.min(&j), *i.max(&j)));
+                }
+            }
+        }
+
+        // Bodies sharing several cells are found more than once
+        pairs.sort_unstable();
+        pairs.dedup();
+        pairs
+    }
+
+    fn query_region(&self, region: Aabb) -> Vec<usize> {
+        let range = self.cell_range(&region);
+        let mut found: Vec<usize> = if Self::cell_count(range) > self.cells.len() as i64 {
+            // Cheaper to look at every occupied cell
+            self.cells.values().flatten().copied().collect()
+        } else {
+            let ((x0, y0), (x1, y1)) = range;
+            (x0..=x1)
+                .flat_map(|x| (y0..=y1).map(move |y| (x, y)))
+                .filter_map(|cell| self.cells.get(&cell))
+                .flatten()
+                .copied()
+                .collect()
+        };
+        found.extend(self.large.iter());
+
+        found.retain(|i| self.aabbs[*i].overlaps(&region));
+        found.sort_unstable();
+        found.dedup();
+        found
+    }
+}
+
+// ====< SWEEP AND PRUNE >====
+/// Sorts the boxes along x and only tests the ones whose x ranges overlap.
+/// The order is kept between steps, so it is almost sorted already when bodies move a little.
+#[derive(Debug, Clone, Default)]
+pub struct SweepAndPrune {
+    aabbs: Vec<Aabb>,
+    order: Vec<usize>,
+}
+
+impl BroadPhase for SweepAndPrune {
+    fn name(&self) -> &'static str {
+        "Sweep and prune"
+    }
+
+    fn update(&mut self, aabbs: &[Aabb]) {
+        self.aabbs = aabbs.to_vec();
+        if self.order.len() != aabbs.len() {
+            self.order = (0..aabbs.len()).collect();
+        }
+
+        // Insertion sort, close to linear on coherent scenes
+        for i in 1..self.order.len() {
+            let mut j = i;
+            while j > 0 && aabbs[self.order[j - 1]].min.x > aabbs[self.order[j]].min.x {
+                self.order.swap(j - 1, j);
+                j -= 1;
+            }
+        }
+    }
+
+    fn pairs(&self) -> Vec<(usize, usize)> {
+        let mut pairs = Vec::new();
+        for (n, i) in self.order.iter().enumerate() {
+            let a = &self.aabbs[*i];
+            for j in self.order[n + 1..].iter() {
+                let b = &self.aabbs[*j];
+                if b.min.x > a.max.x {
+                    break;
+                }
+                if a.overlaps(b) {
+                    pairs.push((*i.min(j), *i.max(j)));
+                }
+            }
+        }
+        pairs.sort_unstable();
+        pairs
+    }
+
+    fn query_region(&self, region: Aabb) -> Vec<usize> {
+        let mut found: Vec<usize> = self.order.iter()
+            .take_while(|i| self.aabbs[**i].min.x <= region.max.x)
+            .filter(|i| self.aabbs[**i].overlaps(&region))
+            .copied()
+            .collect();
+        found.sort_unstable();
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Xorshift, enough for reproducible test scenes
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> f32 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            (self.0 >> 40) as f32 / (1u64 << 24) as f32
+        }
+
+        fn range(&mut self, min: f32, max: f32) -> f32 {
+            min + (max - min) * self.next()
+        }
+    }
+
+    fn random_aabbs(rng: &mut Rng, count: usize) -> Vec<Aabb> {
+        (0..count).map(|i| {
+            let center = Vector2::new(rng.range(-500.0, 500.0), rng.range(-500.0, 500.0));
+            // A few bodies much bigger than the rest, like walls
+            let half_size = if i % 50 == 0 {
+                Vector2::new(rng.range(100.0, 600.0), rng.range(5.0, 20.0))
+            } else {
+                Vector2::new(rng.range(2.0, 30.0), rng.range(2.0, 30.0))
+            };
+            Aabb::from_center(center, half_size)
+        }).collect()
+    }
+
+    #[test]
+    fn broad_phases_agree_with_brute_force() {
+        let mut rng = Rng(0x9e3779b97f4a7c15);
+        let mut broad_phases: Vec<Box<dyn BroadPhase>> = vec![
+            Box::new(BruteForce::default()),
+            Box::new(SpatialHash::default()),
+            Box::new(SpatialHash::new(16.0)),
+            Box::new(SweepAndPrune::default()),
+        ];
+
+        // Several updates, so sweep and prune starts from the order of the last one
+        for _ in 0..5 {
+            let aabbs = random_aabbs(&mut rng, 400);
+            for broad_phase in broad_phases.iter_mut() {
+                broad_phase.update(&aabbs);
+            }
+
+            let expected = broad_phases[0].pairs();
+            assert!(!expected.is_empty());
+            let region = Aabb::from_center(Vector2::new(rng.range(-300.0, 300.0), rng.range(-300.0, 300.0)), Vector2::new(150.0, 80.0));
+            let expected_region = broad_phases[0].query_region(region);
+            for broad_phase in broad_phases[1..].iter() {
+                assert_eq!(broad_phase.pairs(), expected, "{}", broad_phase.name());
+                assert_eq!(broad_phase.query_region(region), expected_region, "{}", broad_phase.name());
+            }
+        }
+    }
+}
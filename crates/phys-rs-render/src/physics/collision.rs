@@ -0,0 +1,291 @@
+use crate::{Brush, math::Vector2, ColorPalette};
+
+use super::{RigidBody, Shape};
+
+// Prefer the first polygon as the reference face when both are about as good, keeps contacts stable
+const REFERENCE_BIAS: f32 = 0.95;
+const CONTACT_RADIUS: f32 = 3.0; // pixels
+const NORMAL_LENGTH: f32 = 20.0; // pixels
+
+// ====< MANIFOLD >====
+/// Where two shapes touch. `normal` points from the first shape to the second one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Manifold {
+    pub normal: Vector2,
+    pub depth: f32,
+    // One or two points in world space
+    pub contacts: Vec<Vector2>,
+}
+
+impl Manifold {
+    pub fn flipped(self) -> Self {
+        Self { normal: -self.normal, ..self }
+    }
+
+    // Debug drawing, contact points and the normal
+    pub fn render(&self, brush: &mut Brush) {
+        for contact in self.contacts.iter() {
+            brush.draw_circle_filled(*contact, brush.px(CONTACT_RADIUS), ColorPalette::RED);
+            brush.draw_arrow(*contact, *contact + self.normal * brush.px(NORMAL_LENGTH), brush.px(1.5), brush.px(5.0), ColorPalette::RED);
+        }
+    }
+}
+
+// ====< BODIES >====
+/// Narrow phase test between two bodies, with the shapes they are drawn with.
+pub fn collide(a: &RigidBody, b: &RigidBody) -> Option<Manifold> {
+    match (&a.shape, &b.shape) {
+        (Shape::Circle { radius: ra }, Shape::Circle { radius: rb }) => collide_circles(a.position, *ra, b.position, *rb),
+        (Shape::Circle { radius }, _) => collide_circle_polygon(a.position, *radius, &b.world_vertices()),
+        (_, Shape::Circle { radius }) => collide_circle_polygon(b.position, *radius, &a.world_vertices()).map(Manifold::flipped),
+        _ => collide_polygons(&a.world_vertices(), &b.world_vertices()),
+    }
+}
+
+// ====< CIRCLES >====
+pub fn collide_circles(a: Vector2, ra: f32, b: Vector2, rb: f32) -> Option<Manifold> {
+    let delta = b - a;
+    let distance = delta.length();
+    if distance > ra + rb {
+        return None;
+    }
+
+    // Concentric circles can be pushed apart in any direction
+    let normal = if distance > f32::EPSILON { delta / distance } else { Vector2::new(1.0, 0.0) };
+    let depth = ra + rb - distance;
+    Some(Manifold {
+        normal,
+        depth,
+        contacts: vec![a + normal * (ra - depth / 2.0)],
+    })
+}
+
+// Polygon vertices are in world space and counter-clockwise
+pub fn collide_circle_polygon(center: Vector2, radius: f32, vertices: &[Vector2]) -> Option<Manifold> {
+    // Face closest to the center
+    let mut separation = f32::NEG_INFINITY;
+    let mut face = 0;
+    for i in 0..vertices.len() {
+        let s = edge_normal(vertices, i).dot(center - vertices[i]);
+        if s > radius {
+            return None;
+        }
+        if s > separation {
+            separation = s;
+            face = i;
+        }
+    }
+
+    let v1 = vertices[face];
+    let v2 = vertices[(face + 1) % vertices.len()];
+    let normal = edge_normal(vertices, face);
+
+    // Center inside the polygon
+    if separation < 0.0 {
+        return Some(Manifold {
+            normal: -normal,
+            depth: radius - separation,
+            contacts: vec![center - normal * separation],
+        });
+    }
+
+    // Closest to one of the corners or to the face itself
+    let corner = if (center - v1).dot(v2 - v1) <= 0.0 {
+        Some(v1)
+    } else if (center - v2).dot(v1 - v2) <= 0.0 {
+        Some(v2)
+    } else {
+        None
+    };
+
+    match corner {
+        Some(corner) => {
+            let distance = (corner - center).length();
+            if distance > radius {
+                return None;
+            }
+            let normal = if distance > f32::EPSILON { (corner - center) / distance } else { -normal };
+            Some(Manifold {
+                normal,
+                depth: radius - distance,
+                contacts: vec![corner],
+            })
+        }
+        None => Some(Manifold {
+            normal: -normal,
+            depth: radius - separation,
+            contacts: vec![center - normal * separation],
+        }),
+    }
+}
+
+// ====< POLYGONS >====
+/// Separating axis test between two convex polygons, vertices in world space and counter-clockwise.
+pub fn collide_polygons(a: &[Vector2], b: &[Vector2]) -> Option<Manifold> {
+    let (separation_a, edge_a) = max_separation(a, b);
+    if separation_a > 0.0 {
+        return None;
+    }
+    let (separation_b, edge_b) = max_separation(b, a);
+    if separation_b > 0.0 {
+        return None;
+    }
+
+    // The reference face is the one with the least penetration
+    let (reference, incident, edge, flip) = if separation_b > separation_a * REFERENCE_BIAS + f32::EPSILON {
+        (b, a, edge_b, true)
+    } else {
+        (a, b, edge_a, false)
+    };
+
+    let normal = edge_normal(reference, edge);
+    let v1 = reference[edge];
+    let v2 = reference[(edge + 1) % reference.len()];
+
+    // Incident face, the one most against the reference normal
+    let incident_edge = (0..incident.len())
+        .min_by(|i, j| edge_normal(incident, *i).dot(normal).total_cmp(&edge_normal(incident, *j).dot(normal)))
+        .unwrap_or(0);
+    let points = [incident[incident_edge], incident[(incident_edge + 1) % incident.len()]];
+
+    // Clip it to the sides of the reference face
+    let tangent = (v2 - v1).normalize();
+    let points = clip(points, -tangent, -tangent.dot(v1))?;
+    let points = clip(points, tangent, tangent.dot(v2))?;
+
+    // Keep what is behind the reference face
+    let mut contacts = Vec::with_capacity(2);
+    let mut depth: f32 = 0.0;
+    for point in points {
+        let separation = normal.dot(point - v1);
+        if separation <= 0.0 {
+            contacts.push(point);
+            depth = depth.max(-separation);
+        }
+    }
+    if contacts.is_empty() {
+        return None;
+    }
+
+    Some(Manifold {
+        normal: if flip { -normal } else { normal },
+        depth,
+        contacts,
+    })
+}
+
+// Outward normal of the edge starting at vertex `i`
+fn edge_normal(vertices: &[Vector2], i: usize) -> Vector2 {
+    let edge = vertices[(i + 1) % vertices.len()] - vertices[i];
+    Vector2::new(edge.y, -edge.x).normalize()
+}
+
+// Largest distance of `b` in front of any face of `a`, and that face
+fn max_separation(a: &[Vector2], b: &[Vector2]) -> (f32, usize) {
+    let mut best = (f32::NEG_INFINITY, 0);
+    for i in 0..a.len() {
+        let normal = edge_normal(a, i);
+        let separation = b.iter().map(|point| normal.dot(*point - a[i])).fold(f32::INFINITY, f32::min);
+        if separation > best.0 {
+            best = (separation, i);
+        }
+    }
+    best
+}
+
+// Keeps the part of the segment where normal . p <= offset
+fn clip(points: [Vector2; 2], normal: Vector2, offset: f32) -> Option<[Vector2; 2]> {
+    let d0 = normal.dot(points[0]) - offset;
+    let d1 = normal.dot(points[1]) - offset;
+
+    match (d0 <= 0.0, d1 <= 0.0) {
+        (true, true) => Some(points),
+        (false, false) => None,
+        _ => {
+            let t = d0 / (d0 - d1);
+            let crossing = points[0] + (points[1] - points[0]) * t;
+            if d0 <= 0.0 { Some([points[0], crossing]) } else { Some([crossing, points[1]]) }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f32 = 1e-4;
+
+    // Counter-clockwise square with half size `half`
+    fn square(center: Vector2, half: f32, angle: f32) -> Vec<Vector2> {
+        [(-half, -half), (half, -half), (half, half), (-half, half)].iter()
+            .map(|(x, y)| center + Vector2::new(*x, *y).rotate(angle))
+            .collect()
+    }
+
+    fn assert_close(a: Vector2, b: Vector2) {
+        assert!((a - b).length() < EPSILON, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn box_on_box_face_contact() {
+        let a = square(Vector2::zero(), 1.0, 0.0);
+        let b = square(Vector2::new(0.5, 1.8), 1.0, 0.0);
+        let manifold = collide_polygons(&a, &b).unwrap();
+        assert_close(manifold.normal, Vector2::new(0.0, 1.0));
+        assert!((manifold.depth - 0.2).abs() < EPSILON);
+        assert_eq!(manifold.contacts.len(), 2);
+
+        // Swapped, the normal still points from the first shape to the second
+        let manifold = collide_polygons(&b, &a).unwrap();
+        assert_close(manifold.normal, Vector2::new(0.0, -1.0));
+        assert!((manifold.depth - 0.2).abs() < EPSILON);
+        assert_eq!(manifold.contacts.len(), 2);
+    }
+
+    #[test]
+    fn box_on_box_corner_contact() {
+        let a = square(Vector2::zero(), 1.0, 0.0);
+        // Standing on a corner, which sinks 0.1 into the top face
+        let b = square(Vector2::new(0.0, 1.0 + std::f32::consts::SQRT_2 - 0.1), 1.0, std::f32::consts::FRAC_PI_4);
+        let manifold = collide_polygons(&a, &b).unwrap();
+        assert_close(manifold.normal, Vector2::new(0.0, 1.0));
+        assert!((manifold.depth - 0.1).abs() < EPSILON);
+        assert_eq!(manifold.contacts.len(), 1);
+        assert_close(manifold.contacts[0], Vector2::new(0.0, 0.9));
+    }
+
+    #[test]
+    fn circle_inside_polygon() {
+        let polygon = square(Vector2::zero(), 1.0, 0.0);
+        let manifold = collide_circle_polygon(Vector2::new(0.2, 0.0), 0.5, &polygon).unwrap();
+        // The circle gets out through the closest face, on the right
+        assert_close(manifold.normal, Vector2::new(-1.0, 0.0));
+        assert!((manifold.depth - 1.3).abs() < EPSILON);
+        assert_eq!(manifold.contacts.len(), 1);
+    }
+
+    #[test]
+    fn circle_against_polygon_face_and_corner() {
+        let polygon = square(Vector2::zero(), 1.0, 0.0);
+        let manifold = collide_circle_polygon(Vector2::new(0.0, 1.3), 0.5, &polygon).unwrap();
+        assert_close(manifold.normal, Vector2::new(0.0, -1.0));
+        assert!((manifold.depth - 0.2).abs() < EPSILON);
+        assert_eq!(manifold.contacts.len(), 1);
+
+        let manifold = collide_circle_polygon(Vector2::new(1.3, 1.3), 0.5, &polygon).unwrap();
+        assert_close(manifold.normal, Vector2::new(-1.0, -1.0).normalize());
+        assert!((manifold.depth - (0.5 - 0.3 * std::f32::consts::SQRT_2)).abs() < EPSILON);
+        assert_eq!(manifold.contacts, vec![Vector2::new(1.0, 1.0)]);
+    }
+
+    #[test]
+    fn separated_shapes() {
+        let a = square(Vector2::zero(), 1.0, 0.0);
+        assert!(collide_polygons(&a, &square(Vector2::new(2.1, 0.0), 1.0, 0.0)).is_none());
+        // Overlapping bounding boxes, but the corner stops short of the face
+        assert!(collide_polygons(&a, &square(Vector2::new(2.0, 2.0), 1.0, std::f32::consts::FRAC_PI_4)).is_none());
+        assert!(collide_circle_polygon(Vector2::new(0.0, 1.6), 0.5, &a).is_none());
+        assert!(collide_circle_polygon(Vector2::new(1.4, 1.4), 0.5, &a).is_none());
+        assert!(collide_circles(Vector2::zero(), 1.0, Vector2::new(2.5, 0.0), 1.0).is_none());
+    }
+}
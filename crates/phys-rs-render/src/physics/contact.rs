@@ -0,0 +1,215 @@
+use crate::{math::Vector2, Units};
+
+use super::{RigidBody, Collision};
+
+const SLOP: f32 = 0.5; // pixels
+const RESTITUTION_THRESHOLD: f32 = 20.0; // pixels per second
+
+// ====< CONTACT SOLVER >====
+/// Resolves collisions with sequential impulses, then pushes the bodies apart.
+#[derive(Debug, Clone, Copy)]
+pub struct ContactSolver {
+    // More iterations make stacks and chains of contacts settle better
+    pub iterations: usize,
+    // Fraction of the penetration removed every step, Baumgarte style
+    pub correction: f32,
+    // Penetration left alone so resting contacts do not jitter, in world units
+    pub slop: f32,
+    // Slower impacts do not bounce, in world units per second
+    pub restitution_threshold: f32,
+}
+
+// Sized for pixel units, see `for_units`
+impl Default for ContactSolver {
+    fn default() -> Self {
+        Self::for_units(&Units::pixels())
+    }
+}
+
+impl ContactSolver {
+    // Slop and restitution threshold of about the same size on screen whatever the scene units are
+    pub fn for_units(units: &Units) -> Self {
+        Self {
+            iterations: 10,
+            correction: 0.4,
+            slop: units.length_from_pixels(SLOP),
+            restitution_threshold: units.length_from_pixels(RESTITUTION_THRESHOLD),
+        }
+    }
+}
+
+struct ContactPoint {
+    position: Vector2,
+    normal_mass: f32,
+    tangent_mass: f32,
+    // Normal velocity the impulses aim for, from restitution
+    target: f32,
+    normal_impulse: f32,
+    tangent_impulse: f32,
+}
+
+struct Constraint {
+    a: usize,
+    b: usize,
+    normal: Vector2,
+    tangent: Vector2,
+    static_friction: f32,
+    kinetic_friction: f32,
+    points: Vec<ContactPoint>,
+}
+
+impl ContactSolver {
+    /// Changes the velocities so that no contact is closing, and stores the impulses in the collisions.
+    pub fn solve(&self, bodies: &mut [RigidBody], collisions: &mut [Collision]) {
+        let mut constraints: Vec<Constraint> = collisions.iter().map(|collision| self.constraint(bodies, collision)).collect();
+
+        for _ in 0..self.iterations {
+            for constraint in constraints.iter_mut() {
+                let (a, b) = pair_mut(bodies, constraint.a, constraint.b);
+                for point in constraint.points.iter_mut() {
+                    // Normal, contacts can only push
+                    let relative = b.velocity_at(point.position) - a.velocity_at(point.position);
+                    let impulse = (point.target - relative.dot(constraint.normal)) * point.normal_mass;
+                    let total = (point.normal_impulse + impulse).max(0.0);
+                    let impulse = total - point.normal_impulse;
+                    point.normal_impulse = total;
+                    apply(a, b, constraint.normal * impulse, point.position);
+
+                    // Friction, sticks until static friction is exceeded, then slides with kinetic friction
+                    let relative = b.velocity_at(point.position) - a.velocity_at(point.position);
+                    let impulse = -relative.dot(constraint.tangent) * point.tangent_mass;
+                    let mut total = point.tangent_impulse + impulse;
+                    if total.abs() > constraint.static_friction * point.normal_impulse {
+                        let limit = constraint.kinetic_friction * point.normal_impulse;
+                        total = total.clamp(-limit, limit);
+                    }
+                    let impulse = total - point.tangent_impulse;
+                    point.tangent_impulse = total;
+                    apply(a, b, constraint.tangent * impulse, point.position);
+                }
+            }
+        }
+
+        for (collision, constraint) in collisions.iter_mut().zip(constraints.iter()) {
+            collision.normal_impulse = constraint.points.iter().map(|point| point.normal_impulse).sum();
+            collision.tangent_impulse = constraint.points.iter().map(|point| point.tangent_impulse).sum();
+        }
+    }
+
+    /// Moves overlapping bodies apart, in proportion to their inverse masses.
+    pub fn correct_positions(&self, bodies: &mut [RigidBody], collisions: &[Collision]) {
+        for collision in collisions.iter() {
+            let (a, b) = pair_mut(bodies, collision.a.index(), collision.b.index());
+            let inv_mass = a.inv_mass() + b.inv_mass();
+            if inv_mass == 0.0 {
+                continue;
+            }
+
+            let depth = (collision.manifold.depth - self.slop).max(0.0);
+            let correction = collision.manifold.normal * (depth * self.correction / inv_mass);
+            a.position -= correction * a.inv_mass();
+            b.position += correction * b.inv_mass();
+        }
+    }
+
+    fn constraint(&self, bodies: &[RigidBody], collision: &Collision) -> Constraint {
+        let (a, b) = (&bodies[collision.a.index()], &bodies[collision.b.index()]);
+        let normal = collision.manifold.normal;
+        let tangent = Vector2::new(-normal.y, normal.x);
+        let restitution = a.restitution.max(b.restitution);
+
+        let effective_mass = |position: Vector2, direction: Vector2| {
+            let (ra, rb) = ((position - a.position).cross(direction), (position - b.position).cross(direction));
+            let k = a.inv_mass() + b.inv_mass() + ra * ra * a.inv_inertia() + rb * rb * b.inv_inertia();
+            if k > 0.0 { 1.0 / k } else { 0.0 }
+        };
+
+        let points = collision.manifold.contacts.iter().map(|position| {
+            let closing = (b.velocity_at(*position) - a.velocity_at(*position)).dot(normal);
+            ContactPoint {
+                position: *position,
+                normal_mass: effective_mass(*position, normal),
+                tangent_mass: effective_mass(*position, tangent),
+                target: if -closing > self.restitution_threshold { -restitution * closing } else { 0.0 },
+                normal_impulse: 0.0,
+                tangent_impulse: 0.0,
+            }
+        }).collect();
+
+        Constraint {
+            a: collision.a.index(),
+            b: collision.b.index(),
+            normal,
+            tangent,
+            static_friction: (a.static_friction * b.static_friction).sqrt(),
+            kinetic_friction: (a.kinetic_friction * b.kinetic_friction).sqrt(),
+            points,
+        }
+    }
+}
+
+// Equal and opposite, `impulse` acts on `b`
+fn apply(a: &mut RigidBody, b: &mut RigidBody, impulse: Vector2, point: Vector2) {
+    a.apply_impulse(-impulse, point);
+    b.apply_impulse(impulse, point);
+}
+
+pub(super) fn pair_mut(bodies: &mut [RigidBody], a: usize, b: usize) -> (&mut RigidBody, &mut RigidBody) {
+    if a < b {
+        let (left, right) = bodies.split_at_mut(b);
+        (&mut left[a], &mut right[0])
+    } else {
+        let (left, right) = bodies.split_at_mut(a);
+        (&mut right[0], &mut left[b])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::{PhysicsWorld, Shape};
+
+    fn head_on(restitution: f32) -> (f32, f32, Vector2, Vector2) {
+        let mut world = PhysicsWorld::new();
+        let (va, vb) = (Vector2::new(300.0, 0.0), Vector2::new(-100.0, 0.0));
+        let a = world.add_body(RigidBody::create(Shape::circle(10.0), 1.0, Vector2::new(-9.5, 0.0)).with_velocity(va).with_restitution(restitution));
+        let b = world.add_body(RigidBody::create(Shape::circle(10.0), 3.0, Vector2::new(9.5, 0.0)).with_velocity(vb).with_restitution(restitution));
+        let before = world.body(a).momentum() + world.body(b).momentum();
+
+        world.step(1e-4);
+        assert_eq!(world.collisions().len(), 1);
+
+        let (a, b) = (world.body(a), world.body(b));
+        let approaching = (va - vb).x;
+        let separating = (b.velocity - a.velocity).x;
+        (approaching, separating, before, a.momentum() + b.momentum())
+    }
+
+    #[test]
+    fn head_on_collision_keeps_momentum() {
+        for restitution in [0.0, 0.5, 0.8, 1.0] {
+            let (approaching, separating, before, after) = head_on(restitution);
+            assert!((after - before).length() < 1e-2, "{:?} != {:?}", after, before);
+            assert!((separating - restitution * approaching).abs() < 1e-2, "e = {}: {} != {}", restitution, separating, restitution * approaching);
+        }
+    }
+
+    #[test]
+    fn slow_impacts_do_not_bounce() {
+        let solver = ContactSolver::default();
+        let mut world = PhysicsWorld::new();
+        let speed = solver.restitution_threshold * 0.5;
+        let a = world.add_body(RigidBody::create(Shape::circle(10.0), 1.0, Vector2::new(-9.99, 0.0)).with_velocity(Vector2::new(speed, 0.0)).with_restitution(1.0));
+        let b = world.add_body(RigidBody::create(Shape::circle(10.0), 1.0, Vector2::new(9.99, 0.0)).with_restitution(1.0));
+        world.step(1e-4);
+        assert!((world.body(b).velocity.x - world.body(a).velocity.x).abs() < 1e-4);
+    }
+
+    #[test]
+    fn tolerances_follow_the_units() {
+        let pixels = ContactSolver::for_units(&Units::pixels());
+        let metres = ContactSolver::for_units(&Units::metres(0.01, Vector2::zero()));
+        assert!((metres.slop - pixels.slop * 0.01).abs() < 1e-6);
+        assert!((metres.restitution_threshold - pixels.restitution_threshold * 0.01).abs() < 1e-6);
+    }
+}
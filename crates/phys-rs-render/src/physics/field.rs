@@ -0,0 +1,304 @@
+use crate::{Brush, math::Vector2, color::Color, ColorPalette, components::physics::{draw_force, ForceStyle}};
+
+use super::{RigidBody, PhysicsWorld, broadphase::Aabb};
+
+const REGION_THICKNESS: f32 = 1.5; // pixels
+const OVERLAY_COLORS: [Color; 4] = [ColorPalette::RED, ColorPalette::GREEN, ColorPalette::BLUE, ColorPalette::WHITE];
+
+// ====< FORCE FIELD >====
+/// Force on every body inside a region, evaluated by the integrator like springs are.
+/// The force acts on the center of mass, so fields never apply a torque, and a body
+/// counts as inside the region when its center is, whatever its size.
+pub trait ForceField {
+    // Force on a body of `mass` at `position` moving with `velocity`
+    fn force(&self, mass: f32, position: Vector2, velocity: Vector2) -> Vector2;
+}
+
+// ====< REGION >====
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Region {
+    #[default]
+    Everywhere,
+    Rect(Aabb),
+    Circle { center: Vector2, radius: f32 },
+}
+
+impl Region {
+    pub fn contains(&self, point: Vector2) -> bool {
+        match self {
+            Region::Everywhere => true,
+            Region::Rect(aabb) => aabb.contains(point),
+            Region::Circle { center, radius } => (point - *center).length() <= *radius,
+        }
+    }
+}
+
+// ====< GRAVITY >====
+/// m * g everywhere, `g` points down on screen for pixel scenes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UniformGravity {
+    pub g: Vector2,
+}
+
+impl UniformGravity {
+    pub fn new(g: Vector2) -> Self {
+        Self { g }
+    }
+}
+
+impl ForceField for UniformGravity {
+    fn force(&self, mass: f32, _position: Vector2, _velocity: Vector2) -> Vector2 {
+        self.g * mass
+    }
+}
+
+/// Newtonian attraction towards `center`, strength is G times the attracting mass.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PointGravity {
+    pub center: Vector2,
+    pub strength: f32,
+    // Keeps the force finite close to the center
+    pub softening: f32,
+}
+
+impl PointGravity {
+    pub fn new(center: Vector2, strength: f32) -> Self {
+        Self { center, strength, softening: 1.0 }
+    }
+
+    pub fn with_softening(self, softening: f32) -> Self {
+        Self { softening, ..self }
+    }
+}
+
+impl ForceField for PointGravity {
+    fn force(&self, mass: f32, position: Vector2, _velocity: Vector2) -> Vector2 {
+        let delta = self.center - position;
+        let distance_squared = delta.dot(delta) + self.softening * self.softening;
+        delta * (self.strength * mass / (distance_squared * distance_squared.sqrt()))
+    }
+}
+
+/// Constant acceleration towards `center`, away from it when negative.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RadialGravity {
+    pub center: Vector2,
+    pub g: f32,
+}
+
+impl RadialGravity {
+    pub fn new(center: Vector2, g: f32) -> Self {
+        Self { center, g }
+    }
+}
+
+impl ForceField for RadialGravity {
+    fn force(&self, mass: f32, position: Vector2, _velocity: Vector2) -> Vector2 {
+        let delta = self.center - position;
+        let distance = delta.length();
+        if distance <= f32::EPSILON {
+            return Vector2::zero();
+        }
+        delta * (self.g * mass / distance)
+    }
+}
+
+// ====< DRAG >====
+/// -b v, for slow motion through a viscous fluid.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LinearDrag {
+    pub b: f32,
+}
+
+impl LinearDrag {
+    pub fn new(b: f32) -> Self {
+        Self { b }
+    }
+}
+
+impl ForceField for LinearDrag {
+    fn force(&self, _mass: f32, _position: Vector2, velocity: Vector2) -> Vector2 {
+        velocity * -self.b
+    }
+}
+
+/// -c |v| v, air resistance at everyday speeds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QuadraticDrag {
+    pub c: f32,
+}
+
+impl QuadraticDrag {
+    pub fn new(c: f32) -> Self {
+        Self { c }
+    }
+}
+
+impl ForceField for QuadraticDrag {
+    fn force(&self, _mass: f32, _position: Vector2, velocity: Vector2) -> Vector2 {
+        velocity * (-self.c * velocity.length())
+    }
+}
+
+/// Linear drag relative to moving air, bodies drift towards `velocity`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Wind {
+    pub velocity: Vector2,
+    pub coefficient: f32,
+}
+
+impl Wind {
+    pub fn new(velocity: Vector2, coefficient: f32) -> Self {
+        Self { velocity, coefficient }
+    }
+}
+
+impl ForceField for Wind {
+    fn force(&self, _mass: f32, _position: Vector2, velocity: Vector2) -> Vector2 {
+        (self.velocity - velocity) * self.coefficient
+    }
+}
+
+// ====< CLOSURES >====
+/// Field from a closure of position and velocity, for anything the built in ones do not cover.
+pub struct FnField<F> {
+    force: F,
+}
+
+impl<F: Fn(Vector2, Vector2) -> Vector2> FnField<F> {
+    pub fn new(force: F) -> Self {
+        Self { force }
+    }
+}
+
+impl<F: Fn(Vector2, Vector2) -> Vector2> ForceField for FnField<F> {
+    fn force(&self, _mass: f32, position: Vector2, velocity: Vector2) -> Vector2 {
+        (self.force)(position, velocity)
+    }
+}
+
+// ====< WORLD >====
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct FieldHandle(pub(crate) usize);
+
+impl FieldHandle {
+    pub fn index(&self) -> usize {
+        self.0
+    }
+}
+
+pub(crate) struct FieldEntry {
+    pub(crate) field: Box<dyn ForceField>,
+    pub(crate) region: Region,
+    pub(crate) enabled: bool,
+}
+
+impl FieldEntry {
+    // Zero outside the region and for static bodies
+    pub(crate) fn force_on(&self, body: &RigidBody, position: Vector2, velocity: Vector2) -> Vector2 {
+        if !self.enabled || body.is_static() || !self.region.contains(position) {
+            return Vector2::zero();
+        }
+        self.field.force(body.mass, position, velocity)
+    }
+}
+
+impl PhysicsWorld {
+    pub fn add_field(&mut self, field: impl ForceField + 'static) -> FieldHandle {
+        self.add_field_in(field, Region::Everywhere)
+    }
+
+    pub fn add_field_in(&mut self, field: impl ForceField + 'static, region: Region) -> FieldHandle {
+        self.fields.push(FieldEntry { field: Box::new(field), region, enabled: true });
+        FieldHandle(self.fields.len() - 1)
+    }
+
+    pub fn set_field_enabled(&mut self, handle: FieldHandle, enabled: bool) {
+        self.fields[handle.0].enabled = enabled;
+    }
+
+    pub fn set_field_region(&mut self, handle: FieldHandle, region: Region) {
+        self.fields[handle.0].region = region;
+    }
+
+    // Sum of every field on a body, at its current position and velocity
+    pub fn field_force(&self, body: &RigidBody) -> Vector2 {
+        self.fields.iter().fold(Vector2::zero(), |sum, field| sum + field.force_on(body, body.position, body.velocity))
+    }
+
+    // Arrows for the force of each field on each body, and the outline of bounded regions
+    pub(crate) fn render_fields(&self, brush: &mut Brush, alpha: f32) {
+        for (i, entry) in self.fields.iter().enumerate().filter(|(_, entry)| entry.enabled) {
+            let color = OVERLAY_COLORS[i % OVERLAY_COLORS.len()];
+            let thickness = brush.px(REGION_THICKNESS);
+            match entry.region {
+                Region::Everywhere => (),
+                Region::Rect(aabb) => {
+                    let corners = [aabb.min, Vector2::new(aabb.max.x, aabb.min.y), aabb.max, Vector2::new(aabb.min.x, aabb.max.y)];
+                    for (j, corner) in corners.iter().enumerate() {
+                        brush.draw_line(*corner, corners[(j + 1) % corners.len()], thickness, color);
+                    }
+                }
+                Region::Circle { center, radius } => brush.draw_circle(center, radius, color, thickness),
+            }
+
+            let style = ForceStyle::new(self.field_arrow_scale, color);
+            for body in self.bodies().iter() {
+                let force = entry.force_on(body, body.position, body.velocity);
+                if force != Vector2::zero() {
+                    draw_force(brush, body.interpolated_point(Vector2::zero(), alpha), force, &style);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::{Shape, Rk4, BodyHandle};
+
+    fn falling_body(world: &mut PhysicsWorld, position: Vector2) -> BodyHandle {
+        world.add_body(RigidBody::create(Shape::circle(1.0), 2.0, position))
+    }
+
+    #[test]
+    fn uniform_gravity_gives_free_fall() {
+        let (g, dt, steps) = (9.81, 0.01, 200);
+        let mut euler = PhysicsWorld::new();
+        let mut rk4 = PhysicsWorld::new().with_integrator(Rk4);
+        let handles = [falling_body(&mut euler, Vector2::zero()), falling_body(&mut rk4, Vector2::zero())];
+        for world in [&mut euler, &mut rk4] {
+            world.add_field(UniformGravity::new(Vector2::new(0.0, g)));
+            for _ in 0..steps {
+                world.step(dt);
+            }
+        }
+
+        let t = dt * steps as f32;
+        let expected = 0.5 * g * t * t;
+        // Semi-implicit Euler is ahead by g dt t / 2, RK4 is exact for constant forces
+        let y = euler.body(handles[0]).position.y;
+        assert!((y - expected).abs() <= 0.5 * g * dt * t * 1.01, "{} != {}", y, expected);
+        let y = rk4.body(handles[1]).position.y;
+        assert!((y - expected).abs() < 1e-3, "{} != {}", y, expected);
+        assert_eq!(rk4.body(handles[1]).position.x, 0.0);
+    }
+
+    #[test]
+    fn circle_region_only_acts_inside() {
+        let mut world = PhysicsWorld::new();
+        let inside = falling_body(&mut world, Vector2::new(3.0, 0.0));
+        let outside = falling_body(&mut world, Vector2::new(6.0, 0.0));
+        world.add_field_in(UniformGravity::new(Vector2::new(0.0, 10.0)), Region::Circle { center: Vector2::zero(), radius: 5.0 });
+
+        assert_eq!(world.field_force(world.body(outside)), Vector2::zero());
+        assert_eq!(world.field_force(world.body(inside)), Vector2::new(0.0, 20.0));
+        for _ in 0..10 {
+            world.step(0.01);
+        }
+        assert_eq!(world.body(outside).position, Vector2::new(6.0, 0.0));
+        assert_eq!(world.body(outside).velocity, Vector2::zero());
+        assert!(world.body(inside).velocity.y > 0.0);
+    }
+}
@@ -0,0 +1,204 @@
+use crate::math::Vector2;
+
+/// Writes the accelerations for time `t`, positions `q` and velocities `v` into the last slice.
+pub type AccelerationFn<'a> = dyn FnMut(f32, &[f32], &[f32], &mut [f32]) + 'a;
+
+// ====< INTEGRATOR >====
+/// Advances a second order system (positions and velocities) by one step.
+pub trait Integrator {
+    fn name(&self) -> &'static str;
+
+    fn step(&self, t: f32, dt: f32, q: &mut [f32], v: &mut [f32], accel: &mut AccelerationFn);
+
+    // Single particle, e.g. for objects that do not live in a PhysicsWorld
+    fn step_vector(&self, t: f32, dt: f32, position: &mut Vector2, velocity: &mut Vector2, accel: &mut dyn FnMut(f32, Vector2, Vector2) -> Vector2) {
+        let mut q = [position.x, position.y];
+        let mut v = [velocity.x, velocity.y];
+        self.step(t, dt, &mut q, &mut v, &mut |t, q, v, a| {
+            let acceleration = accel(t, Vector2::new(q[0], q[1]), Vector2::new(v[0], v[1]));
+            a[0] = acceleration.x;
+            a[1] = acceleration.y;
+        });
+        *position = Vector2::new(q[0], q[1]);
+        *velocity = Vector2::new(v[0], v[1]);
+    }
+}
+
+// out = x + y * scale
+fn add_scaled(out: &mut [f32], x: &[f32], y: &[f32], scale: f32) {
+    for ((out, x), y) in out.iter_mut().zip(x).zip(y) {
+        *out = x + y * scale;
+    }
+}
+
+// ====< EULER >====
+/// x += v dt, then v += a dt. Gains energy on every oscillator.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ExplicitEuler;
+
+impl Integrator for ExplicitEuler {
+    fn name(&self) -> &'static str {
+        "Explicit Euler"
+    }
+
+    fn step(&self, t: f32, dt: f32, q: &mut [f32], v: &mut [f32], accel: &mut AccelerationFn) {
+        let mut a = vec![0.0; q.len()];
+        accel(t, q, v, &mut a);
+        for i in 0..q.len() {
+            q[i] += v[i] * dt;
+            v[i] += a[i] * dt;
+        }
+    }
+}
+
+/// v += a dt, then x += v dt. Symplectic, energy oscillates but does not drift.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SemiImplicitEuler;
+
+impl Integrator for SemiImplicitEuler {
+    fn name(&self) -> &'static str {
+        "Semi-implicit Euler"
+    }
+
+    fn step(&self, t: f32, dt: f32, q: &mut [f32], v: &mut [f32], accel: &mut AccelerationFn) {
+        let mut a = vec![0.0; q.len()];
+        accel(t, q, v, &mut a);
+        for i in 0..q.len() {
+            v[i] += a[i] * dt;
+            q[i] += v[i] * dt;
+        }
+    }
+}
+
+// ====< VERLET >====
+/// Second order and symplectic. Velocity dependent forces use the half step velocity.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct VelocityVerlet;
+
+impl Integrator for VelocityVerlet {
+    fn name(&self) -> &'static str {
+        "Velocity Verlet"
+    }
+
+    fn step(&self, t: f32, dt: f32, q: &mut [f32], v: &mut [f32], accel: &mut AccelerationFn) {
+        let mut a = vec![0.0; q.len()];
+        accel(t, q, v, &mut a);
+        for i in 0..q.len() {
+            v[i] += a[i] * dt * 0.5;
+            q[i] += v[i] * dt;
+        }
+
+        accel(t + dt, q, v, &mut a);
+        for i in 0..q.len() {
+            v[i] += a[i] * dt * 0.5;
+        }
+    }
+}
+
+// ====< RUNGE KUTTA >====
+/// Classic fourth order Runge-Kutta. Very accurate, but slowly loses energy over long runs.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Rk4;
+
+impl Integrator for Rk4 {
+    fn name(&self) -> &'static str {
+        "RK4"
+    }
+
+    fn step(&self, t: f32, dt: f32, q: &mut [f32], v: &mut [f32], accel: &mut AccelerationFn) {
+        let n = q.len();
+        let (mut q_tmp, mut v_tmp) = (vec![0.0; n], vec![0.0; n]);
+
+        // k1
+        let mut a1 = vec![0.0; n];
+        accel(t, q, v, &mut a1);
+        let v1 = v.to_vec();
+
+        // k2
+        add_scaled(&mut q_tmp, q, &v1, dt * 0.5);
+        add_scaled(&mut v_tmp, v, &a1, dt * 0.5);
+        let mut a2 = vec![0.0; n];
+        accel(t + dt * 0.5, &q_tmp, &v_tmp, &mut a2);
+        let v2 = v_tmp.clone();
+
+        // k3
+        add_scaled(&mut q_tmp, q, &v2, dt * 0.5);
+        add_scaled(&mut v_tmp, v, &a2, dt * 0.5);
+        let mut a3 = vec![0.0; n];
+        accel(t + dt * 0.5, &q_tmp, &v_tmp, &mut a3);
+        let v3 = v_tmp.clone();
+
+        // k4
+        add_scaled(&mut q_tmp, q, &v3, dt);
+        add_scaled(&mut v_tmp, v, &a3, dt);
+        let mut a4 = vec![0.0; n];
+        accel(t + dt, &q_tmp, &v_tmp, &mut a4);
+        let v4 = v_tmp;
+
+        for i in 0..n {
+            q[i] += dt / 6.0 * (v1[i] + 2.0 * v2[i] + 2.0 * v3[i] + v4[i]);
+            v[i] += dt / 6.0 * (a1[i] + 2.0 * a2[i] + 2.0 * a3[i] + a4[i]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DT: f32 = 0.05;
+    const STEPS: usize = 1000;
+
+    // Unit mass on a unit spring from x = 1, returns the largest energy drift and the largest position error
+    fn oscillate(integrator: &dyn Integrator) -> (f32, f32) {
+        let (mut q, mut v) = ([1.0], [0.0]);
+        let (mut drift, mut error): (f32, f32) = (0.0, 0.0);
+        for i in 0..STEPS {
+            integrator.step(i as f32 * DT, DT, &mut q, &mut v, &mut |_, q, _, a| a[0] = -q[0]);
+            let energy = 0.5 * (q[0] * q[0] + v[0] * v[0]);
+            drift = drift.max((energy - 0.5).abs());
+            error = error.max((q[0] - ((i + 1) as f32 * DT).cos()).abs());
+        }
+        (drift, error)
+    }
+
+    #[test]
+    fn explicit_euler_gains_energy() {
+        let (drift, _) = oscillate(&ExplicitEuler);
+        // Every step multiplies the energy by 1 + dt^2
+        assert!(drift > 5.0, "{}", drift);
+    }
+
+    #[test]
+    fn semi_implicit_euler_stays_bounded() {
+        let (drift, _) = oscillate(&SemiImplicitEuler);
+        assert!(drift < DT, "{}", drift);
+    }
+
+    #[test]
+    fn higher_order_integrators_are_more_accurate() {
+        let (_, euler) = oscillate(&SemiImplicitEuler);
+        let (verlet_drift, verlet) = oscillate(&VelocityVerlet);
+        let (rk4_drift, rk4) = oscillate(&Rk4);
+        assert!(verlet < euler / 5.0, "{} {}", verlet, euler);
+        assert!(rk4 < verlet / 100.0, "{} {}", rk4, verlet);
+        assert!(verlet_drift < DT * DT);
+        assert!(rk4_drift < 1e-4);
+    }
+
+    #[test]
+    fn orbit_energy() {
+        // Circular orbit around the origin with GM = 1 and radius 1
+        let orbit = |integrator: &dyn Integrator| {
+            let (mut position, mut velocity) = (Vector2::new(1.0, 0.0), Vector2::new(0.0, 1.0));
+            for i in 0..STEPS {
+                integrator.step_vector(i as f32 * DT, DT, &mut position, &mut velocity, &mut |_, p, _| p * (-1.0 / p.length().powi(3)));
+            }
+            0.5 * velocity.dot(velocity) - 1.0 / position.length()
+        };
+        // Starts at -0.5
+        assert!(orbit(&ExplicitEuler) > -0.3);
+        assert!((orbit(&SemiImplicitEuler) + 0.5).abs() < 0.05);
+        assert!((orbit(&Rk4) + 0.5).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,490 @@
+use crate::{Brush, math::Vector2, color::Color, ColorPalette, components::physics::{draw_wall, WallStyle}};
+
+use super::{RigidBody, Shape, PhysicsWorld, BodyHandle, contact::pair_mut};
+
+const LINE_THICKNESS: f32 = 3.0; // pixels
+const ROPE_THICKNESS: f32 = 2.0; // pixels
+const PIVOT_RADIUS: f32 = 5.0; // pixels
+const SUPPORT_SIZE: f32 = 16.0; // pixels
+const SLIDER_SIZE: f32 = 14.0; // pixels
+const ROPE_SEGMENTS: usize = 16;
+
+// ====< HANDLES >====
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct JointHandle(pub(crate) usize);
+
+impl JointHandle {
+    pub fn index(&self) -> usize {
+        self.0
+    }
+}
+
+// ====< KINDS >====
+#[derive(Debug, Clone, PartialEq)]
+pub enum JointKind {
+    // Keeps the anchors `length` apart, like a massless rod
+    Distance { length: f32 },
+    // Anchors can get closer, but not further apart than `length`
+    Rope { length: f32 },
+    // Anchors stay together, the bodies turn freely around them. A pin when `b` is the world
+    Revolute,
+    // `a` slides along `axis` fixed to `b` and does not turn relative to it
+    Prismatic { axis: Vector2, reference_angle: f32 },
+    // Rope over two fixed points, |a - ground_a| + ratio * |b - ground_b| stays `length`
+    Pulley { ground_a: Vector2, ground_b: Vector2, ratio: f32, length: f32 },
+}
+
+// ====< JOINT >====
+/// Constraint between two bodies, or between a body and the world.
+/// The constructors take world space anchors and convert them with the bodies as they are now.
+#[derive(Debug, Clone)]
+pub struct Joint {
+    pub a: BodyHandle,
+    // `None` attaches to the world, `anchor_b` is then in world space
+    pub b: Option<BodyHandle>,
+    // In body space
+    pub anchor_a: Vector2,
+    pub anchor_b: Vector2,
+    pub kind: JointKind,
+
+    pub color: Color,
+    pub visible: bool,
+}
+
+impl Joint {
+    fn create(world: &PhysicsWorld, a: BodyHandle, anchor_a: Vector2, b: Option<BodyHandle>, anchor_b: Vector2, kind: JointKind) -> Self {
+        Self {
+            a,
+            b,
+            anchor_a: world.body(a).local_point(anchor_a),
+            anchor_b: match b {
+                Some(b) => world.body(b).local_point(anchor_b),
+                None => anchor_b,
+            },
+            kind,
+            color: ColorPalette::WHITE,
+            visible: true,
+        }
+    }
+
+    pub fn distance(world: &PhysicsWorld, a: BodyHandle, anchor_a: Vector2, b: Option<BodyHandle>, anchor_b: Vector2) -> Self {
+        let length = (anchor_b - anchor_a).length();
+        Self::create(world, a, anchor_a, b, anchor_b, JointKind::Distance { length })
+    }
+
+    pub fn rope(world: &PhysicsWorld, a: BodyHandle, anchor_a: Vector2, b: Option<BodyHandle>, anchor_b: Vector2, length: f32) -> Self {
+        Self::create(world, a, anchor_a, b, anchor_b, JointKind::Rope { length })
+    }
+
+    // Hinge between a body and a fixed point
+    pub fn pin(world: &PhysicsWorld, a: BodyHandle, point: Vector2) -> Self {
+        Self::create(world, a, point, None, point, JointKind::Revolute)
+    }
+
+    pub fn revolute(world: &PhysicsWorld, a: BodyHandle, b: BodyHandle, point: Vector2) -> Self {
+        Self::create(world, a, point, Some(b), point, JointKind::Revolute)
+    }
+
+    // `axis` is in world space, `a` slides along it through its current position
+    pub fn prismatic(world: &PhysicsWorld, a: BodyHandle, b: Option<BodyHandle>, axis: Vector2) -> Self {
+        assert!(axis.length() > f32::EPSILON, "prismatic joint axis must not be zero");
+        let point = world.body(a).position;
+        let (axis, reference_angle) = match b {
+            Some(b) => (axis.rotate(-world.body(b).angle), world.body(a).angle - world.body(b).angle),
+            None => (axis, world.body(a).angle),
+        };
+        let kind = JointKind::Prismatic { axis: axis.normalize(), reference_angle };
+        Self::create(world, a, point, b, point, kind)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn pulley(world: &PhysicsWorld, a: BodyHandle, anchor_a: Vector2, ground_a: Vector2, b: BodyHandle, anchor_b: Vector2, ground_b: Vector2, ratio: f32) -> Self {
+        let length = (anchor_a - ground_a).length() + ratio * (anchor_b - ground_b).length();
+        Self::create(world, a, anchor_a, Some(b), anchor_b, JointKind::Pulley { ground_a, ground_b, ratio, length })
+    }
+
+    pub fn with_color(self, color: Color) -> Self {
+        Self { color, ..self }
+    }
+
+    // ====< SOLVER >====
+    // Moves the bodies so the constraint holds again
+    pub(crate) fn solve_position(&self, bodies: &mut [RigidBody]) {
+        self.with_bodies(bodies, |joint, a, b| joint.position_step(a, b));
+    }
+
+    // Removes the relative velocity the constraint does not allow
+    pub(crate) fn solve_velocity(&self, bodies: &mut [RigidBody]) {
+        self.with_bodies(bodies, |joint, a, b| joint.velocity_step(a, b));
+    }
+
+    // The world is a static body at `anchor_b`, so every joint is solved between two bodies
+    fn with_bodies(&self, bodies: &mut [RigidBody], solve: impl FnOnce(&Self, &mut RigidBody, &mut RigidBody)) {
+        match self.b {
+            Some(b) if b == self.a => (),
+            Some(b) => {
+                let (a, b) = pair_mut(bodies, self.a.0, b.0);
+                solve(self, a, b);
+            }
+            None => {
+                let mut ground = RigidBody::fixed(Shape::circle(0.0), self.anchor_b);
+                solve(self, &mut bodies[self.a.0], &mut ground);
+            }
+        }
+    }
+
+    fn local_b(&self) -> Vector2 {
+        if self.b.is_some() { self.anchor_b } else { Vector2::zero() }
+    }
+
+    fn position_step(&self, a: &mut RigidBody, b: &mut RigidBody) {
+        let (pa, pb) = (a.world_point(self.anchor_a), b.world_point(self.local_b()));
+        match &self.kind {
+            JointKind::Distance { length } | JointKind::Rope { length } => {
+                let delta = pb - pa;
+                let distance = delta.length();
+                let error = distance - length;
+                if distance <= f32::EPSILON || (matches!(self.kind, JointKind::Rope { .. }) && error <= 0.0) {
+                    return;
+                }
+                let normal = delta / distance;
+                let lambda = -error * effective_mass(a, pa, b, pb, normal);
+                displace(a, pa, normal * -lambda);
+                displace(b, pb, normal * lambda);
+            }
+            JointKind::Revolute => {
+                let lambda = -solve_point(a, pa, b, pb, pb - pa);
+                displace(a, pa, -lambda);
+                displace(b, pb, lambda);
+            }
+            JointKind::Prismatic { axis, reference_angle } => {
+                let angular_mass = a.inv_inertia() + b.inv_inertia();
+                if angular_mass > 0.0 {
+                    let lambda = -(a.angle - b.angle - reference_angle) / angular_mass;
+                    a.angle += lambda * a.inv_inertia();
+                    b.angle -= lambda * b.inv_inertia();
+                }
+
+                let (pa, pb) = (a.world_point(self.anchor_a), b.world_point(self.local_b()));
+                let normal = axis.rotate(b.angle);
+                let normal = Vector2::new(-normal.y, normal.x);
+                let lambda = -normal.dot(pa - pb) * effective_mass(b, pa, a, pa, normal);
+                displace(a, pa, normal * lambda);
+                displace(b, pa, normal * -lambda);
+            }
+            JointKind::Pulley { ground_a, ground_b, ratio, length } => {
+                let Some((ua, la, ub, lb)) = pulley_directions(pa, *ground_a, pb, *ground_b) else { return };
+                let error = la + ratio * lb - length;
+                let lambda = -error * pulley_mass(a, pa, ua, b, pb, ub, *ratio);
+                displace(a, pa, ua * lambda);
+                displace(b, pb, ub * (ratio * lambda));
+            }
+        }
+    }
+
+    fn velocity_step(&self, a: &mut RigidBody, b: &mut RigidBody) {
+        let (pa, pb) = (a.world_point(self.anchor_a), b.world_point(self.local_b()));
+        let relative = b.velocity_at(pb) - a.velocity_at(pa);
+        match &self.kind {
+            JointKind::Distance { length } | JointKind::Rope { length } => {
+                let delta = pb - pa;
+                let distance = delta.length();
+                if distance <= f32::EPSILON {
+                    return;
+                }
+                let normal = delta / distance;
+                let speed = relative.dot(normal);
+                // A slack rope, or one that is getting shorter, does nothing
+                if matches!(self.kind, JointKind::Rope { .. }) && (distance < *length || speed <= 0.0) {
+                    return;
+                }
+                let lambda = -speed * effective_mass(a, pa, b, pb, normal);
+                a.apply_impulse(normal * -lambda, pa);
+                b.apply_impulse(normal * lambda, pb);
+            }
+            JointKind::Revolute => {
+                let lambda = -solve_point(a, pa, b, pb, relative);
+                a.apply_impulse(-lambda, pa);
+                b.apply_impulse(lambda, pb);
+            }
+            JointKind::Prismatic { axis, .. } => {
+                let angular_mass = a.inv_inertia() + b.inv_inertia();
+                if angular_mass > 0.0 {
+                    let lambda = -(a.angular_velocity - b.angular_velocity) / angular_mass;
+                    a.angular_velocity += lambda * a.inv_inertia();
+                    b.angular_velocity -= lambda * b.inv_inertia();
+                }
+
+                let normal = axis.rotate(b.angle);
+                let normal = Vector2::new(-normal.y, normal.x);
+                let speed = (a.velocity_at(pa) - b.velocity_at(pa)).dot(normal);
+                let lambda = -speed * effective_mass(b, pa, a, pa, normal);
+                a.apply_impulse(normal * lambda, pa);
+                b.apply_impulse(normal * -lambda, pa);
+            }
+            JointKind::Pulley { ground_a, ground_b, ratio, .. } => {
+                let Some((ua, _, ub, _)) = pulley_directions(pa, *ground_a, pb, *ground_b) else { return };
+                let speed = a.velocity_at(pa).dot(ua) + ratio * b.velocity_at(pb).dot(ub);
+                let lambda = -speed * pulley_mass(a, pa, ua, b, pb, ub, *ratio);
+                a.apply_impulse(ua * lambda, pa);
+                b.apply_impulse(ub * (ratio * lambda), pb);
+            }
+        }
+    }
+
+    // ====< RENDER >====
+    pub fn render(&self, brush: &mut Brush, bodies: &[RigidBody], alpha: f32) {
+        if !self.visible {
+            return;
+        }
+
+        let a = &bodies[self.a.0];
+        let pa = a.interpolated_point(self.anchor_a, alpha);
+        let pb = match self.b {
+            Some(b) => bodies[b.0].interpolated_point(self.anchor_b, alpha),
+            None => self.anchor_b,
+        };
+        let (thickness, pivot) = (brush.px(LINE_THICKNESS), brush.px(PIVOT_RADIUS));
+
+        match &self.kind {
+            JointKind::Distance { .. } => {
+                brush.draw_line_rounded(pa, pb, thickness, self.color);
+                draw_pivot(brush, pa, pivot * 0.7, self.color);
+                draw_pivot(brush, pb, pivot * 0.7, self.color);
+            }
+            JointKind::Rope { length } => {
+                draw_rope(brush, pa, pb, *length, self.color);
+            }
+            JointKind::Revolute => {
+                if self.b.is_none() {
+                    draw_support(brush, pb, self.color);
+                }
+                draw_pivot(brush, pb, pivot, self.color);
+            }
+            JointKind::Prismatic { axis, .. } => {
+                let angle = self.b.map_or(0.0, |b| bodies[b.0].previous_angle + (bodies[b.0].angle - bodies[b.0].previous_angle) * alpha);
+                let axis = axis.rotate(angle);
+                let reach = ((pa - pb).dot(axis).abs() + brush.px(SLIDER_SIZE * 3.0)).max(brush.px(SLIDER_SIZE * 4.0));
+                brush.draw_line(pb - axis * reach, pb + axis * reach, brush.px(ROPE_THICKNESS), self.color);
+                brush._draw_quad_border_raw(pa, Vector2::new(brush.px(SLIDER_SIZE * 1.5), brush.px(SLIDER_SIZE)), ColorPalette::BACKGROUND, brush.px(ROPE_THICKNESS), self.color, -axis.angle(), 0.0);
+            }
+            JointKind::Pulley { ground_a, ground_b, .. } => {
+                brush.draw_line(pa, *ground_a, brush.px(ROPE_THICKNESS), self.color);
+                brush.draw_line(pb, *ground_b, brush.px(ROPE_THICKNESS), self.color);
+
+                // One wheel touching both ground points, or two small ones if they are far apart
+                let span = (*ground_b - *ground_a).length();
+                if span > f32::EPSILON && span < brush.px(SUPPORT_SIZE * 8.0) {
+                    let center = (*ground_a + *ground_b) / 2.0;
+                    brush.draw_circle_filled(center, span / 2.0, ColorPalette::BACKGROUND);
+                    brush.draw_circle(center, span / 2.0, self.color, brush.px(ROPE_THICKNESS));
+                    draw_pivot(brush, center, pivot, self.color);
+                } else {
+                    draw_pivot(brush, *ground_a, pivot, self.color);
+                    draw_pivot(brush, *ground_b, pivot, self.color);
+                }
+            }
+        }
+    }
+}
+
+// 1 / (J M^-1 J^T) for an impulse along `direction` at `pa` on `a` and `pb` on `b`
+fn effective_mass(a: &RigidBody, pa: Vector2, b: &RigidBody, pb: Vector2, direction: Vector2) -> f32 {
+    let (ra, rb) = ((pa - a.position).cross(direction), (pb - b.position).cross(direction));
+    let k = a.inv_mass() + b.inv_mass() + ra * ra * a.inv_inertia() + rb * rb * b.inv_inertia();
+    if k > 0.0 { 1.0 / k } else { 0.0 }
+}
+
+// Impulse that cancels `error` between two coincident points, from the 2x2 effective mass
+fn solve_point(a: &RigidBody, pa: Vector2, b: &RigidBody, pb: Vector2, error: Vector2) -> Vector2 {
+    let (ra, rb) = (pa - a.position, pb - b.position);
+    let (ma, mb, ia, ib) = (a.inv_mass(), b.inv_mass(), a.inv_inertia(), b.inv_inertia());
+
+    let k11 = ma + mb + ia * ra.y * ra.y + ib * rb.y * rb.y;
+    let k12 = -ia * ra.x * ra.y - ib * rb.x * rb.y;
+    let k22 = ma + mb + ia * ra.x * ra.x + ib * rb.x * rb.x;
+    let determinant = k11 * k22 - k12 * k12;
+    if determinant.abs() <= f32::EPSILON {
+        return Vector2::zero();
+    }
+    Vector2::new(k22 * error.x - k12 * error.y, k11 * error.y - k12 * error.x) / determinant
+}
+
+// Moves a body like `apply_impulse` changes its velocity
+fn displace(body: &mut RigidBody, point: Vector2, impulse: Vector2) {
+    body.angle += (point - body.position).cross(impulse) * body.inv_inertia();
+    body.position += impulse * body.inv_mass();
+}
+
+// Unit vectors from the ground points to the anchors, and the rope lengths on both sides
+fn pulley_directions(pa: Vector2, ground_a: Vector2, pb: Vector2, ground_b: Vector2) -> Option<(Vector2, f32, Vector2, f32)> {
+    let (la, lb) = ((pa - ground_a).length(), (pb - ground_b).length());
+    if la <= f32::EPSILON || lb <= f32::EPSILON {
+        return None;
+    }
+    Some(((pa - ground_a) / la, la, (pb - ground_b) / lb, lb))
+}
+
+fn pulley_mass(a: &RigidBody, pa: Vector2, ua: Vector2, b: &RigidBody, pb: Vector2, ub: Vector2, ratio: f32) -> f32 {
+    let (ra, rb) = ((pa - a.position).cross(ua), (pb - b.position).cross(ub));
+    let k = a.inv_mass() + ra * ra * a.inv_inertia() + ratio * ratio * (b.inv_mass() + rb * rb * b.inv_inertia());
+    if k > 0.0 { 1.0 / k } else { 0.0 }
+}
+
+fn draw_pivot(brush: &mut Brush, center: Vector2, radius: f32, color: Color) {
+    brush.draw_circle_filled(center, radius, ColorPalette::BACKGROUND);
+    brush.draw_circle(center, radius, color, brush.px(1.5));
+}
+
+// Triangle under a fixed pivot, standing on a short hatched ground
+fn draw_support(brush: &mut Brush, pivot: Vector2, color: Color) {
+    let (size, down) = (brush.px(SUPPORT_SIZE), Vector2::new(0.0, brush.units.y_sign()));
+    let side = Vector2::new(size * 0.6, 0.0);
+    let base = pivot + down * size;
+
+    brush.draw_polygon_filled(&[pivot, base + side, base - side], ColorPalette::BACKGROUND);
+    brush.draw_line_rounded(pivot, base + side, brush.px(1.5), color);
+    brush.draw_line_rounded(pivot, base - side, brush.px(1.5), color);
+
+    let style = WallStyle { color, hatch_color: color, thickness: 1.5, hatch_length: 6.0, hatch_spacing: 6.0, hatch_thickness: 1.0, ..Default::default() };
+    draw_wall(brush, base - side * 1.5, base + side * 1.5, -down, &style);
+}
+
+// Straight when taut, sagging downwards on screen when slack
+fn draw_rope(brush: &mut Brush, a: Vector2, b: Vector2, length: f32, color: Color) {
+    let thickness = brush.px(ROPE_THICKNESS);
+    let distance = (b - a).length();
+    if distance >= length || distance <= f32::EPSILON {
+        brush.draw_line_rounded(a, b, thickness, color);
+        return;
+    }
+
+    // Parabola with about the right length, good enough for a drawing
+    let sag = (length * length - distance * distance).sqrt() / 2.0;
+    let down = Vector2::new(0.0, brush.units.y_sign());
+    let mut previous = a;
+    for i in 1..=ROPE_SEGMENTS {
+        let t = i as f32 / ROPE_SEGMENTS as f32;
+        let point = a + (b - a) * t + down * (4.0 * sag * t * (1.0 - t));
+        brush.draw_line_rounded(previous, point, thickness, color);
+        previous = point;
+    }
+}
+
+// ====< WORLD >====
+impl PhysicsWorld {
+    pub fn add_joint(&mut self, joint: Joint) -> JointHandle {
+        self.joints.push(joint);
+        JointHandle(self.joints.len() - 1)
+    }
+
+    pub fn joint(&self, handle: JointHandle) -> &Joint {
+        &self.joints[handle.0]
+    }
+
+    pub fn joint_mut(&mut self, handle: JointHandle) -> &mut Joint {
+        &mut self.joints[handle.0]
+    }
+
+    pub fn joints(&self) -> &[Joint] {
+        &self.joints
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::UniformGravity;
+
+    const DT: f32 = 1.0 / 120.0;
+    const G: f32 = 100.0;
+
+    fn world_with_gravity() -> PhysicsWorld {
+        let mut world = PhysicsWorld::new();
+        world.detect_collisions = false;
+        world.add_field(UniformGravity::new(Vector2::new(0.0, G)));
+        world
+    }
+
+    fn ball(world: &mut PhysicsWorld, position: Vector2) -> BodyHandle {
+        world.add_body(RigidBody::create(Shape::circle(2.0), 1.0, position))
+    }
+
+    #[test]
+    fn distance_pendulum_keeps_its_length() {
+        let mut world = world_with_gravity();
+        let body = ball(&mut world, Vector2::new(30.0, 0.0));
+        world.add_joint(Joint::distance(&world, body, Vector2::new(30.0, 0.0), None, Vector2::zero()));
+
+        let mut lowest: f32 = 0.0;
+        for _ in 0..600 {
+            world.step(DT);
+            let position = world.body(body).position;
+            assert!((position.length() - 30.0).abs() < 0.05, "{}", position.length());
+            lowest = lowest.max(position.y);
+        }
+        // It actually swings through the bottom
+        assert!(lowest > 29.0);
+    }
+
+    #[test]
+    fn rope_is_slack_below_its_length() {
+        let mut world = world_with_gravity();
+        let body = ball(&mut world, Vector2::new(0.0, 10.0));
+        world.add_joint(Joint::rope(&world, body, Vector2::new(0.0, 10.0), None, Vector2::zero(), 30.0));
+
+        // Free fall until the rope is taut, 20 px take sqrt(2 * 20 / g) seconds
+        let mut t = 0.0;
+        while t + DT < (40.0 / G).sqrt() - DT {
+            world.step(DT);
+            t += DT;
+            assert!((world.body(body).velocity.y - G * t).abs() < 1e-3);
+        }
+
+        for _ in 0..600 {
+            world.step(DT);
+            assert!(world.body(body).position.length() < 30.05);
+        }
+        assert!((world.body(body).position.y - 30.0).abs() < 0.05);
+        assert!(world.body(body).velocity.length() < 1.0);
+    }
+
+    #[test]
+    fn prismatic_body_stays_on_its_axis() {
+        let mut world = world_with_gravity();
+        let body = world.add_body(RigidBody::create(Shape::rectangle(8.0, 4.0), 1.0, Vector2::zero())
+            .with_velocity(Vector2::new(-20.0, 5.0))
+            .with_angular_velocity(3.0));
+        world.add_joint(Joint::prismatic(&world, body, None, Vector2::new(1.0, 1.0)));
+
+        for _ in 0..600 {
+            world.step(DT);
+            let body = world.body(body);
+            assert!((body.position.x - body.position.y).abs() < 0.05, "{:?}", body.position);
+            assert!(body.angle.abs() < 1e-3);
+        }
+        // Gravity slides it down the axis
+        assert!(world.body(body).position.y > 100.0);
+    }
+
+    #[test]
+    fn pulley_keeps_the_rope_length() {
+        let mut world = world_with_gravity();
+        let (ground_a, ground_b, ratio) = (Vector2::new(-20.0, 0.0), Vector2::new(20.0, 0.0), 2.0);
+        let a = ball(&mut world, Vector2::new(-20.0, 50.0));
+        let b = ball(&mut world, Vector2::new(20.0, 40.0));
+        world.add_joint(Joint::pulley(&world, a, Vector2::new(-20.0, 50.0), ground_a, b, Vector2::new(20.0, 40.0), ground_b, ratio));
+
+        let length = 50.0 + ratio * 40.0;
+        for _ in 0..600 {
+            world.step(DT);
+            let (pa, pb) = (world.body(a).position, world.body(b).position);
+            let rope = (pa - ground_a).length() + ratio * (pb - ground_b).length();
+            assert!((rope - length).abs() < 0.05, "{} != {}", rope, length);
+        }
+        // Equal masses, `b` only rises half as far as `a` falls so `a` goes down
+        let (drop, rise) = (world.body(a).position.y - 50.0, 40.0 - world.body(b).position.y);
+        assert!(drop > 1.0);
+        assert!((rise - drop / ratio).abs() < 0.1, "{} {}", rise, drop);
+    }
+}
@@ -0,0 +1,23 @@
+mod body;
+mod world;
+mod integrator;
+mod rk45;
+mod ode;
+mod collision;
+mod broadphase;
+mod contact;
+mod joint;
+mod spring;
+mod field;
+
+pub use body::{RigidBody, Shape};
+pub use world::{PhysicsWorld, BodyHandle, Collision};
+pub use integrator::{Integrator, AccelerationFn, ExplicitEuler, SemiImplicitEuler, VelocityVerlet, Rk4};
+pub use rk45::{Rk45, DerivativeFn, SolverError, SolverStats};
+pub use ode::{OdeSystem, OdeObject, FnOde};
+pub use collision::{Manifold, collide, collide_circles, collide_circle_polygon, collide_polygons};
+pub use broadphase::{Aabb, BroadPhase, BroadPhaseStats, BruteForce, SpatialHash, SweepAndPrune};
+pub use contact::ContactSolver;
+pub use joint::{Joint, JointKind, JointHandle};
+pub use spring::{Spring, SpringHandle};
+pub use field::{ForceField, Region, FieldHandle, UniformGravity, PointGravity, RadialGravity, LinearDrag, QuadraticDrag, Wind, FnField};
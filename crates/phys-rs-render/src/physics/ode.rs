@@ -0,0 +1,212 @@
+use crate::{Brush, Renderer, PhysRenderable, DataCollector};
+
+use super::{Rk45, SolverError};
+
+// ====< ODE SYSTEM >====
+/// A model defined by x' = f(t, x) and a way to draw its state.
+/// Wrap it in an `OdeObject` to add it to a `Scene`.
+pub trait OdeSystem {
+    fn dimension(&self) -> usize;
+    fn derivative(&self, t: f64, state: &[f64], derivative: &mut [f64]);
+    fn draw(&self, brush: &mut Brush, t: f64, state: &[f64]);
+
+    // Names of the state entries in the DataCollector, `None` skips recording
+    fn labels(&self) -> Option<Vec<String>> {
+        Some((0..self.dimension()).map(|i| format!("x{}", i)).collect())
+    }
+
+    // Extra values recorded every step, e.g. the energy
+    fn observables(&self, _t: f64, _state: &[f64]) -> Vec<(String, f64)> {
+        Vec::new()
+    }
+}
+
+// ====< CLOSURES >====
+/// `OdeSystem` built from closures, for models that do not need their own type.
+pub struct FnOde<F, D> {
+    dimension: usize,
+    derivative: F,
+    draw: D,
+    labels: Vec<String>,
+}
+
+impl<F, D> FnOde<F, D>
+where
+    F: Fn(f64, &[f64], &mut [f64]),
+    D: Fn(&mut Brush, f64, &[f64]),
+{
+    pub fn new(dimension: usize, derivative: F, draw: D) -> Self {
+        let labels = (0..dimension).map(|i| format!("x{}", i)).collect();
+        Self { dimension, derivative, draw, labels }
+    }
+
+    pub fn with_labels(self, labels: &[&str]) -> Self {
+        Self {
+            labels: labels.iter().map(|label| label.to_string()).collect(),
+            ..self
+        }
+    }
+}
+
+impl<F, D> OdeSystem for FnOde<F, D>
+where
+    F: Fn(f64, &[f64], &mut [f64]),
+    D: Fn(&mut Brush, f64, &[f64]),
+{
+    fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    fn derivative(&self, t: f64, state: &[f64], derivative: &mut [f64]) {
+        (self.derivative)(t, state, derivative)
+    }
+
+    fn draw(&self, brush: &mut Brush, t: f64, state: &[f64]) {
+        (self.draw)(brush, t, state)
+    }
+
+    fn labels(&self) -> Option<Vec<String>> {
+        Some(self.labels.clone())
+    }
+}
+
+// ====< ODE OBJECT >====
+/// Steps an `OdeSystem` with an adaptive solver, records it and draws it.
+pub struct OdeObject<S: OdeSystem> {
+    pub system: S,
+    pub solver: Rk45,
+    // Prefix of the recorded channels
+    pub name: String,
+
+    state: Vec<f64>,
+    previous_state: Vec<f64>,
+    time: f64,
+    previous_time: f64,
+    labels: Option<Vec<String>>,
+
+    // Set when the solver gave up, the state is frozen from then on
+    pub error: Option<SolverError>,
+}
+
+impl<S: OdeSystem> OdeObject<S> {
+    pub fn new(name: &str, system: S, initial_state: &[f64]) -> Self {
+        assert_eq!(initial_state.len(), system.dimension(), "initial state does not match the system dimension");
+
+        let labels = system.labels();
+        Self {
+            system,
+            solver: Rk45::default().with_name(name),
+            name: name.to_string(),
+            state: initial_state.to_vec(),
+            previous_state: initial_state.to_vec(),
+            time: 0.0,
+            previous_time: 0.0,
+            labels,
+            error: None,
+        }
+    }
+
+    pub fn with_solver(self, solver: Rk45) -> Self {
+        let solver = solver.with_name(&self.name);
+        Self { solver, ..self }
+    }
+
+    pub fn state(&self) -> &[f64] {
+        &self.state
+    }
+
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
+    // Replaces the state, e.g. when the user drags something
+    pub fn set_state(&mut self, state: &[f64]) {
+        self.state.copy_from_slice(state);
+        self.previous_state.copy_from_slice(state);
+        self.solver.reset();
+    }
+
+    fn record(&self, data_collector: &mut DataCollector) {
+        if let Some(labels) = &self.labels {
+            for (label, value) in labels.iter().zip(self.state.iter()) {
+                data_collector.record(&format!("{}.{}", self.name, label), *value as f32);
+            }
+        }
+        for (label, value) in self.system.observables(self.time, &self.state) {
+            data_collector.record(&format!("{}.{}", self.name, label), value as f32);
+        }
+    }
+}
+
+impl<S: OdeSystem> PhysRenderable for OdeObject<S> {
+    fn render(&self, brush: &mut Brush, _renderer: &mut Renderer, _dt: f32, alpha: f32, _frame: u128) {
+        let time = self.previous_time + (self.time - self.previous_time) * alpha as f64;
+        // Dense output of the solver, linear only when it has none, e.g. right after `set_state`
+        let mut state = vec![0.0; self.state.len()];
+        if !self.solver.dense_output(time, &mut state) {
+            for (i, out) in state.iter_mut().enumerate() {
+                *out = self.previous_state[i] + (self.state[i] - self.previous_state[i]) * alpha as f64;
+            }
+        }
+        self.system.draw(brush, time, &state);
+    }
+
+    fn update(&mut self, dt: f32, _frame: u128, mut data_collector: Option<&mut DataCollector>) {
+        if self.error.is_some() {
+            return;
+        }
+
+        self.previous_state.copy_from_slice(&self.state);
+        self.previous_time = self.time;
+        let system = &self.system;
+        let result = self.solver.integrate(self.time, self.time + dt as f64, &mut self.state, &mut |t, state, derivative| {
+            system.derivative(t, state, derivative);
+        }, data_collector.as_deref_mut());
+
+        match result {
+            Ok(_) => self.time += dt as f64,
+            Err(error) => {
+                // The solver may have stopped halfway, keep the last good state
+                self.state.copy_from_slice(&self.previous_state);
+                self.error = Some(error);
+            }
+        }
+
+        if let Some(data_collector) = data_collector {
+            self.record(data_collector);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exponential_decay() {
+        let system = FnOde::new(1, |_t, x: &[f64], dx: &mut [f64]| dx[0] = -2.0 * x[0], |_: &mut Brush, _: f64, _: &[f64]| ())
+            .with_labels(&["x"]);
+        let mut object = OdeObject::new("decay", system, &[1.0]);
+        let mut data_collector = DataCollector::new();
+
+        let dt = 0.01;
+        for frame in 0..100 {
+            // Like `Scene`, the collector is stamped with the time at the end of the step
+            data_collector.begin_step((frame + 1) as f32 * dt, frame);
+            object.update(dt, frame, Some(&mut data_collector));
+            data_collector.end_step();
+        }
+
+        assert!(object.error.is_none());
+        assert!((object.time() - 1.0).abs() < 1e-5);
+        let expected = (-2.0f64).exp();
+        assert!((object.state()[0] - expected).abs() < 1e-6, "{} != {}", object.state()[0], expected);
+
+        let channel = data_collector.channel("decay.x").unwrap();
+        assert_eq!(channel.len(), 100);
+        let last = channel.last().unwrap();
+        assert!((last.time - 1.0).abs() < 1e-5);
+        assert!((last.value as f64 - expected).abs() < 1e-6);
+        assert!(data_collector.channel("decay.dt").is_some());
+    }
+}
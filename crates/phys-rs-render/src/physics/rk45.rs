@@ -0,0 +1,385 @@
+use std::fmt;
+
+use crate::DataCollector;
+
+/// Writes dy/dt at time `t` and state `y` into the last slice.
+pub type DerivativeFn<'a> = dyn FnMut(f64, &[f64], &mut [f64]) + 'a;
+
+// ====< TABLEAU >====
+// Dormand-Prince 5(4), see Hairer, Norsett and Wanner, Solving ODEs I
+const C: [f64; 7] = [0.0, 1.0 / 5.0, 3.0 / 10.0, 4.0 / 5.0, 8.0 / 9.0, 1.0, 1.0];
+const A: [[f64; 6]; 7] = [
+    [0.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+    [1.0 / 5.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+    [3.0 / 40.0, 9.0 / 40.0, 0.0, 0.0, 0.0, 0.0],
+    [44.0 / 45.0, -56.0 / 15.0, 32.0 / 9.0, 0.0, 0.0, 0.0],
+    [19372.0 / 6561.0, -25360.0 / 2187.0, 64448.0 / 6561.0, -212.0 / 729.0, 0.0, 0.0],
+    [9017.0 / 3168.0, -355.0 / 33.0, 46732.0 / 5247.0, 49.0 / 176.0, -5103.0 / 18656.0, 0.0],
+    // Same as the fifth order weights, so the last stage is the first one of the next step
+    [35.0 / 384.0, 0.0, 500.0 / 1113.0, 125.0 / 192.0, -2187.0 / 6784.0, 11.0 / 84.0],
+];
+// Fifth minus fourth order weights
+const E: [f64; 7] = [71.0 / 57600.0, 0.0, -71.0 / 16695.0, 71.0 / 1920.0, -17253.0 / 339200.0, 22.0 / 525.0, -1.0 / 40.0];
+// Dense output
+const D: [f64; 7] = [
+    -12715105075.0 / 11282082432.0, 0.0, 87487479700.0 / 32700410799.0, -10690763975.0 / 1880347072.0,
+    701980252875.0 / 199316789632.0, -1453857185.0 / 822651844.0, 69997945.0 / 29380423.0,
+];
+
+const SAFETY: f64 = 0.9;
+const MIN_FACTOR: f64 = 0.2;
+const MAX_FACTOR: f64 = 5.0;
+
+// ====< ERRORS >====
+#[derive(Debug, Clone, PartialEq)]
+pub enum SolverError {
+    // The error could not be controlled without going below `min_step`
+    StepTooSmall { time: f64, step: f64 },
+    TooManySteps { time: f64 },
+}
+
+impl fmt::Display for SolverError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SolverError::StepTooSmall { time, step } => write!(f, "step size {} too small at t = {}", step, time),
+            SolverError::TooManySteps { time } => write!(f, "too many steps, stopped at t = {}", time),
+        }
+    }
+}
+
+impl std::error::Error for SolverError {}
+
+// ====< STATS >====
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SolverStats {
+    pub accepted: usize,
+    pub rejected: usize,
+    pub evaluations: usize,
+}
+
+// Coefficients of the interpolating polynomial over one accepted step
+#[derive(Debug, Clone)]
+struct DenseStep {
+    t: f64,
+    h: f64,
+    coefficients: [Vec<f64>; 5],
+}
+
+// ====< RK45 >====
+/// Adaptive Dormand-Prince 5(4) solver for first order systems y' = f(t, y).
+/// The step size is kept between calls, so it can be driven one frame at a time.
+#[derive(Debug, Clone)]
+pub struct Rk45 {
+    pub rtol: f64,
+    pub atol: f64,
+    pub min_step: f64,
+    pub max_step: f64,
+    // Per call to `integrate`
+    pub max_steps: usize,
+
+    // Step sizes are recorded to `<name>.dt`, rejections to `<name>.rejected`
+    pub name: String,
+
+    step: Option<f64>,
+    // Every step accepted by the last call to `integrate`
+    dense: Vec<DenseStep>,
+    stats: SolverStats,
+}
+
+impl Default for Rk45 {
+    fn default() -> Self {
+        Self::new(1e-6, 1e-9)
+    }
+}
+
+impl Rk45 {
+    pub fn new(rtol: f64, atol: f64) -> Self {
+        Self {
+            rtol,
+            atol,
+            min_step: 1e-12,
+            max_step: f64::INFINITY,
+            max_steps: 100_000,
+            name: "rk45".to_string(),
+            step: None,
+            dense: Vec::new(),
+            stats: SolverStats::default(),
+        }
+    }
+
+    pub fn with_name(self, name: &str) -> Self {
+        Self { name: name.to_string(), ..self }
+    }
+
+    pub fn with_max_step(self, max_step: f64) -> Self {
+        Self { max_step, ..self }
+    }
+
+    // Totals since the solver was created
+    pub fn stats(&self) -> SolverStats {
+        self.stats
+    }
+
+    // Size the next step will try, if a step was taken already
+    pub fn step_size(&self) -> Option<f64> {
+        self.step
+    }
+
+    // Forgets the step size and dense output, e.g. after the state was changed by hand
+    pub fn reset(&mut self) {
+        self.step = None;
+        self.dense.clear();
+    }
+
+    /// Advances `y` from `t` to exactly `t_end`, taking as many steps as the tolerances need.
+    /// The data collector time is taken to be the time at `t_end`, like the rows `Scene` records after a step.
+    pub fn integrate(&mut self, t: f64, t_end: f64, y: &mut [f64], f: &mut DerivativeFn, mut data_collector: Option<&mut DataCollector>) -> Result<SolverStats, SolverError> {
+        let n = y.len();
+        let direction = if t_end >= t { 1.0 } else { -1.0 };
+        let mut stats = SolverStats::default();
+        let mut t = t;
+
+        let mut k: [Vec<f64>; 7] = Default::default();
+        for stage in k.iter_mut() {
+            *stage = vec![0.0; n];
+        }
+        let mut y_stage = vec![0.0; n];
+        let mut y_new = vec![0.0; n];
+
+        f(t, y, &mut k[0]);
+        stats.evaluations += 1;
+
+        let mut h = match self.step {
+            Some(h) => h,
+            None => self.initial_step(y, &k[0]),
+        };
+        let end_time = data_collector.as_ref().map(|data_collector| data_collector.time()).unwrap_or(0.0);
+        self.dense.clear();
+
+        while (t_end - t) * direction > 0.0 {
+            if stats.accepted + stats.rejected >= self.max_steps {
+                self.stats = add_stats(self.stats, stats);
+                return Err(SolverError::TooManySteps { time: t });
+            }
+
+            // Do not step past the end, but remember the size the error control wanted
+            let remaining = (t_end - t).abs();
+            let truncated = h >= remaining;
+            let step = h.min(remaining) * direction;
+
+            // Stages
+            for s in 1..7 {
+                for i in 0..n {
+                    let mut sum = 0.0;
+                    for (j, a) in A[s][..s].iter().enumerate() {
+                        sum += a * k[j][i];
+                    }
+                    y_stage[i] = y[i] + step * sum;
+                }
+                f(t + C[s] * step, &y_stage, &mut k[s]);
+            }
+            stats.evaluations += 6;
+            // The last stage was evaluated at the fifth order solution
+            y_new.copy_from_slice(&y_stage);
+
+            // Error estimate
+            let mut error = 0.0;
+            for i in 0..n {
+                let mut e = 0.0;
+                for (s, weight) in E.iter().enumerate() {
+                    e += weight * k[s][i];
+                }
+                let scale = self.atol + self.rtol * y[i].abs().max(y_new[i].abs());
+                error += (step * e / scale).powi(2);
+            }
+            let error = (error / n.max(1) as f64).sqrt();
+
+            if error <= 1.0 {
+                // Accept
+                self.dense.push(dense_step(t, step, y, &y_new, &k));
+                t = if truncated { t_end } else { t + step };
+                y.copy_from_slice(&y_new);
+                let first = std::mem::take(&mut k[6]);
+                k[6] = std::mem::replace(&mut k[0], first);
+                stats.accepted += 1;
+
+                if let Some(data_collector) = data_collector.as_deref_mut() {
+                    let time = end_time - (t_end - t).abs() as f32;
+                    data_collector.record_at(&format!("{}.dt", self.name), time, step.abs() as f32);
+                }
+
+                let factor = if error == 0.0 { MAX_FACTOR } else { (SAFETY * error.powf(-0.2)).clamp(MIN_FACTOR, MAX_FACTOR) };
+                if !truncated {
+                    h = (h * factor).min(self.max_step);
+                }
+            } else {
+                // Reject, NaN ends up here as well
+                stats.rejected += 1;
+                let factor = if error.is_finite() { (SAFETY * error.powf(-0.2)).clamp(MIN_FACTOR, 1.0) } else { MIN_FACTOR };
+                h = step.abs() * factor;
+                if h < self.min_step {
+                    self.stats = add_stats(self.stats, stats);
+                    return Err(SolverError::StepTooSmall { time: t, step: h });
+                }
+            }
+        }
+
+        if let Some(data_collector) = data_collector {
+            data_collector.record(&format!("{}.rejected", self.name), stats.rejected as f32);
+        }
+
+        self.step = Some(h);
+        self.stats = add_stats(self.stats, stats);
+        Ok(stats)
+    }
+
+    /// State at time `t` within the last call to `integrate`, from the fourth order interpolant.
+    pub fn dense_output(&self, t: f64, out: &mut [f64]) -> bool {
+        let theta_of = |dense: &DenseStep| (t - dense.t) / dense.h;
+        let dense = match self.dense.iter().find(|dense| (-1e-9..=1.0 + 1e-9).contains(&theta_of(dense))) {
+            Some(dense) => dense,
+            None => return false,
+        };
+        let theta = theta_of(dense);
+
+        let [r1, r2, r3, r4, r5] = &dense.coefficients;
+        let theta1 = 1.0 - theta;
+        for (i, out) in out.iter_mut().enumerate() {
+            *out = r1[i] + theta * (r2[i] + theta1 * (r3[i] + theta * (r4[i] + theta1 * r5[i])));
+        }
+        true
+    }
+
+    // Time range covered by `dense_output`
+    pub fn dense_range(&self) -> Option<(f64, f64)> {
+        let (first, last) = (self.dense.first()?, self.dense.last()?);
+        Some((first.t, last.t + last.h))
+    }
+
+    fn initial_step(&self, y: &[f64], dy: &[f64]) -> f64 {
+        let scale = |i: usize| self.atol + self.rtol * y[i].abs();
+        let d0 = (0..y.len()).map(|i| (y[i] / scale(i)).powi(2)).sum::<f64>().sqrt();
+        let d1 = (0..y.len()).map(|i| (dy[i] / scale(i)).powi(2)).sum::<f64>().sqrt();
+        let h = if d0 < 1e-5 || d1 < 1e-5 { 1e-6 } else { 0.01 * d0 / d1 };
+        h.clamp(self.min_step, self.max_step)
+    }
+}
+
+fn dense_step(t: f64, h: f64, y: &[f64], y_new: &[f64], k: &[Vec<f64>; 7]) -> DenseStep {
+    let n = y.len();
+    let mut coefficients: [Vec<f64>; 5] = Default::default();
+    for coefficient in coefficients.iter_mut() {
+        *coefficient = vec![0.0; n];
+    }
+
+    for i in 0..n {
+        let difference = y_new[i] - y[i];
+        let slope = h * k[0][i] - difference;
+        coefficients[0][i] = y[i];
+        coefficients[1][i] = difference;
+        coefficients[2][i] = slope;
+        coefficients[3][i] = difference - h * k[6][i] - slope;
+        coefficients[4][i] = h * D.iter().enumerate().map(|(s, d)| d * k[s][i]).sum::<f64>();
+    }
+
+    DenseStep { t, h, coefficients }
+}
+
+fn add_stats(a: SolverStats, b: SolverStats) -> SolverStats {
+    SolverStats {
+        accepted: a.accepted + b.accepted,
+        rejected: a.rejected + b.rejected,
+        evaluations: a.evaluations + b.evaluations,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // x'' = -x, starting at x = 1, v = 0
+    fn oscillator(_t: f64, y: &[f64], dy: &mut [f64]) {
+        dy[0] = y[1];
+        dy[1] = -y[0];
+    }
+
+    #[test]
+    fn dense_output_covers_the_whole_call() {
+        let mut solver = Rk45::new(1e-8, 1e-10);
+        let mut y = [1.0, 0.0];
+        solver.integrate(0.0, 2.0, &mut y, &mut oscillator, None).unwrap();
+        assert!(solver.stats().accepted > 1);
+        assert_eq!(solver.dense_range(), Some((0.0, 2.0)));
+
+        let mut out = [0.0; 2];
+        for i in 0..=20 {
+            let t = i as f64 * 0.1;
+            assert!(solver.dense_output(t, &mut out));
+            assert!((out[0] - t.cos()).abs() < 1e-5);
+            assert!((out[1] + t.sin()).abs() < 1e-5);
+        }
+        assert!(!solver.dense_output(2.5, &mut out));
+    }
+
+    #[test]
+    fn step_sizes_share_the_time_axis_of_the_collector() {
+        let mut solver = Rk45::default().with_name("osc");
+        let mut data_collector = DataCollector::new();
+        let mut y = [1.0, 0.0];
+
+        // Like `Scene`, the collector is stamped with the time at the end of the step
+        data_collector.begin_step(1.0, 0);
+        solver.integrate(0.0, 1.0, &mut y, &mut oscillator, Some(&mut data_collector)).unwrap();
+
+        let samples = &data_collector.channel("osc.dt").unwrap().samples;
+        let last = samples.back().unwrap();
+        assert!((last.time - 1.0).abs() < 1e-6);
+        let total: f32 = samples.iter().map(|sample| sample.value).sum();
+        assert!((total - 1.0).abs() < 1e-5);
+        assert!(samples.iter().all(|sample| sample.time > 0.0 && sample.time <= 1.0));
+    }
+
+    #[test]
+    fn error_stays_within_tolerance() {
+        let tolerance = 1e-6;
+        let mut solver = Rk45::new(tolerance, tolerance * 1e-3);
+        let mut y = [1.0, 0.0];
+        let mut t = 0.0;
+
+        // Five periods, one frame at a time like `OdeObject` does
+        while t < 10.0 * std::f64::consts::PI {
+            solver.integrate(t, t + 0.05, &mut y, &mut oscillator, None).unwrap();
+            t += 0.05;
+            let error = ((y[0] - t.cos()).powi(2) + (y[1] + t.sin()).powi(2)).sqrt();
+            assert!(error < tolerance, "error {} at t = {}", error, t);
+        }
+    }
+
+    #[test]
+    fn step_size_follows_the_stiffness() {
+        // Relaxes towards cos(t), a thousand times faster after t = 2
+        let mut relax = |t: f64, y: &[f64], dy: &mut [f64]| {
+            let rate = if t < 2.0 { 1.0 } else { 1000.0 };
+            dy[0] = -rate * (y[0] - t.cos());
+        };
+        let mut solver = Rk45::new(1e-6, 1e-9).with_name("relax");
+        let mut data_collector = DataCollector::new();
+        let mut y = [1.0];
+
+        data_collector.begin_step(4.0, 0);
+        solver.integrate(0.0, 4.0, &mut y, &mut relax, Some(&mut data_collector)).unwrap();
+        let steps: Vec<(f32, f32)> = data_collector.channel("relax.dt").unwrap().samples.iter().map(|sample| (sample.time, sample.value)).collect();
+
+        // Grows from the initial guess over the smooth stretch
+        let smooth: Vec<f32> = steps.iter().filter(|(time, _)| *time < 1.9).map(|(_, dt)| *dt).collect();
+        assert!(smooth.windows(2).take(5).all(|pair| pair[1] > pair[0]));
+        let largest = smooth.iter().cloned().fold(0.0, f32::max);
+        assert!(largest > smooth[0] * 100.0);
+
+        // Shrinks to the stability limit once it is stiff
+        let stiff: Vec<f32> = steps.iter().filter(|(time, _)| *time > 2.5 && *time < 3.9).map(|(_, dt)| *dt).collect();
+        assert!(stiff.iter().all(|dt| *dt < 0.01));
+        assert!(stiff.iter().cloned().fold(0.0, f32::max) < largest / 10.0);
+        assert!((y[0] - 4.0f64.cos()).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,288 @@
+use crate::{Brush, math::Vector2, DataCollector, components::physics::{draw_spring, draw_dashpot, SpringStyle, DashpotStyle}};
+
+use super::{RigidBody, PhysicsWorld, BodyHandle};
+
+// ====< HANDLES >====
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SpringHandle(pub(crate) usize);
+
+impl SpringHandle {
+    pub fn index(&self) -> usize {
+        self.0
+    }
+}
+
+// ====< SPRING >====
+/// Hooke's law spring with optional linear damping, between two bodies or a body and a fixed anchor.
+/// Forces are evaluated inside the integrator, so RK4 and Verlet see them at every stage.
+#[derive(Debug, Clone)]
+pub struct Spring {
+    pub a: BodyHandle,
+    // `None` attaches to the world, `anchor_b` is then in world space
+    pub b: Option<BodyHandle>,
+    // In body space
+    pub anchor_a: Vector2,
+    pub anchor_b: Vector2,
+
+    pub k: f32,
+    pub rest_length: f32,
+    // Force per unit of stretching speed
+    pub damping: f32,
+
+    // Tension and extension are recorded to `<name>.tension` and `<name>.extension`
+    pub name: Option<String>,
+    pub visible: bool,
+    pub style: SpringStyle,
+    // Drawn next to the spring when there is damping
+    pub dashpot_style: DashpotStyle,
+}
+
+impl Spring {
+    // Anchors are in world space and converted with the bodies as they are now
+    pub fn create(world: &PhysicsWorld, a: BodyHandle, anchor_a: Vector2, b: Option<BodyHandle>, anchor_b: Vector2, k: f32, rest_length: f32) -> Self {
+        Self {
+            a,
+            b,
+            anchor_a: world.body(a).local_point(anchor_a),
+            anchor_b: match b {
+                Some(b) => world.body(b).local_point(anchor_b),
+                None => anchor_b,
+            },
+            k,
+            rest_length,
+            damping: 0.0,
+            name: None,
+            visible: true,
+            style: SpringStyle::default(),
+            dashpot_style: DashpotStyle::default(),
+        }
+    }
+
+    pub fn with_damping(self, damping: f32) -> Self {
+        Self { damping, ..self }
+    }
+
+    pub fn with_name(self, name: &str) -> Self {
+        Self { name: Some(name.to_string()), ..self }
+    }
+
+    pub fn with_style(self, style: SpringStyle) -> Self {
+        Self { style, ..self }
+    }
+
+    // Anchors in world space
+    pub fn endpoints(&self, bodies: &[RigidBody]) -> (Vector2, Vector2) {
+        let a = bodies[self.a.0].world_point(self.anchor_a);
+        let b = match self.b {
+            Some(b) => bodies[b.0].world_point(self.anchor_b),
+            None => self.anchor_b,
+        };
+        (a, b)
+    }
+
+    pub fn length(&self, bodies: &[RigidBody]) -> f32 {
+        let (a, b) = self.endpoints(bodies);
+        (b - a).length()
+    }
+
+    pub fn extension(&self, bodies: &[RigidBody]) -> f32 {
+        self.length(bodies) - self.rest_length
+    }
+
+    // Positive when the spring pulls the bodies together, damping included
+    pub fn tension(&self, bodies: &[RigidBody]) -> f32 {
+        let (pa, pb) = self.endpoints(bodies);
+        self.tension_between(bodies, pa, pb)
+    }
+
+    // Tension with the anchors at `pa` and `pb`, e.g. where they are drawn
+    fn tension_between(&self, bodies: &[RigidBody], pa: Vector2, pb: Vector2) -> f32 {
+        let va = bodies[self.a.0].velocity_at(pa);
+        let vb = self.b.map_or(Vector2::zero(), |b| bodies[b.0].velocity_at(pb));
+        self.force(pa, va, pb, vb).1
+    }
+
+    pub fn potential_energy(&self, bodies: &[RigidBody]) -> f32 {
+        let extension = self.extension(bodies);
+        0.5 * self.k * extension * extension
+    }
+
+    // Force on `a` and the tension, from the anchor positions and velocities
+    fn force(&self, pa: Vector2, va: Vector2, pb: Vector2, vb: Vector2) -> (Vector2, f32) {
+        let delta = pb - pa;
+        let length = delta.length();
+        if length <= f32::EPSILON {
+            return (Vector2::zero(), 0.0);
+        }
+        let direction = delta / length;
+        let tension = self.k * (length - self.rest_length) + self.damping * (vb - va).dot(direction);
+        (direction * tension, tension)
+    }
+
+    // Adds the spring accelerations for the integrator state `q`, `v`
+    pub(crate) fn accelerate(&self, bodies: &[RigidBody], q: &[f32], v: &[f32], a: &mut [f32], state_per_body: usize) {
+        let point = |i: usize, local: Vector2| {
+            let (q, v) = (&q[i * state_per_body..], &v[i * state_per_body..]);
+            let r = local.rotate(q[2]);
+            (Vector2::new(q[0], q[1]) + r, Vector2::new(v[0], v[1]) + Vector2::new(-r.y, r.x) * v[2], r)
+        };
+
+        let (pa, va, ra) = point(self.a.0, self.anchor_a);
+        let (pb, vb, rb) = match self.b {
+            Some(b) => point(b.0, self.anchor_b),
+            None => (self.anchor_b, Vector2::zero(), Vector2::zero()),
+        };
+        let (force, _) = self.force(pa, va, pb, vb);
+
+        let mut push = |i: usize, force: Vector2, r: Vector2| {
+            let (body, out) = (&bodies[i], &mut a[i * state_per_body..]);
+            out[0] += force.x * body.inv_mass();
+            out[1] += force.y * body.inv_mass();
+            out[2] += r.cross(force) * body.inv_inertia();
+        };
+        push(self.a.0, force, ra);
+        if let Some(b) = self.b {
+            push(b.0, -force, rb);
+        }
+    }
+
+    pub fn render(&self, brush: &mut Brush, bodies: &[RigidBody], alpha: f32) {
+        if !self.visible {
+            return;
+        }
+
+        let a = bodies[self.a.0].interpolated_point(self.anchor_a, alpha);
+        let b = match self.b {
+            Some(b) => bodies[b.0].interpolated_point(self.anchor_b, alpha),
+            None => self.anchor_b,
+        };
+        // From the drawn length, so the color matches the drawn extension
+        let tension = self.tension_between(bodies, a, b);
+        if self.damping <= 0.0 {
+            draw_spring(brush, b, a, tension, &self.style);
+            return;
+        }
+
+        // Spring and damper side by side, joined by a bar at both ends
+        let length = (a - b).length();
+        if length <= f32::EPSILON {
+            return;
+        }
+        let dir = (a - b) / length;
+        let offset = Vector2::new(-dir.y, dir.x) * brush.px(self.style.width.max(self.dashpot_style.width) * 0.75);
+        let thickness = brush.px(self.style.thickness);
+        brush.draw_line_rounded(b - offset, b + offset, thickness, self.style.color);
+        brush.draw_line_rounded(a - offset, a + offset, thickness, self.style.color);
+        draw_spring(brush, b + offset, a + offset, tension, &SpringStyle { end_color: None, ..self.style.clone() });
+        draw_dashpot(brush, b - offset, a - offset, self.rest_length, &self.dashpot_style);
+    }
+}
+
+// ====< WORLD >====
+impl PhysicsWorld {
+    pub fn add_spring(&mut self, spring: Spring) -> SpringHandle {
+        self.springs.push(spring);
+        SpringHandle(self.springs.len() - 1)
+    }
+
+    pub fn spring(&self, handle: SpringHandle) -> &Spring {
+        &self.springs[handle.0]
+    }
+
+    pub fn spring_mut(&mut self, handle: SpringHandle) -> &mut Spring {
+        &mut self.springs[handle.0]
+    }
+
+    pub fn springs(&self) -> &[Spring] {
+        &self.springs
+    }
+
+    pub fn spring_energy(&self) -> f32 {
+        self.springs.iter().map(|spring| spring.potential_energy(self.bodies())).sum()
+    }
+
+    // Tension and extension of every spring, unnamed ones are `spring<index>`
+    pub fn record(&self, data_collector: &mut DataCollector) {
+        for (i, spring) in self.springs.iter().enumerate() {
+            let name = spring.name.clone().unwrap_or_else(|| format!("spring{}", i));
+            data_collector.record(&format!("{}.tension", name), spring.tension(self.bodies()));
+            data_collector.record(&format!("{}.extension", name), spring.extension(self.bodies()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::{Shape, Rk4};
+
+    // Mass `m` on a spring to the origin with rest length 10, stretched by 2 along x
+    fn oscillator(k: f32, m: f32, damping: f32) -> (PhysicsWorld, BodyHandle) {
+        let mut world = PhysicsWorld::new().with_integrator(Rk4);
+        world.detect_collisions = false;
+        let body = world.add_body(RigidBody::create(Shape::circle(1.0), m, Vector2::new(12.0, 0.0)));
+        let spring = Spring::create(&world, body, Vector2::new(12.0, 0.0), None, Vector2::zero(), k, 10.0).with_damping(damping);
+        world.add_spring(spring);
+        (world, body)
+    }
+
+    fn energy(world: &PhysicsWorld) -> f32 {
+        world.kinetic_energy() + world.spring_energy()
+    }
+
+    #[test]
+    fn oscillates_at_the_natural_frequency() {
+        let (k, m, dt) = (40.0, 2.5, 0.001);
+        let (mut world, body) = oscillator(k, m, 0.0);
+
+        // Times where the body passes the rest length going inwards, one per period
+        let mut crossings = Vec::new();
+        let mut previous = world.body(body).position.x;
+        for i in 0..5000 {
+            world.step(dt);
+            let x = world.body(body).position.x;
+            if previous > 10.0 && x <= 10.0 {
+                crossings.push(i as f32 * dt);
+            }
+            previous = x;
+        }
+
+        let period = (crossings[crossings.len() - 1] - crossings[0]) / (crossings.len() - 1) as f32;
+        let expected = std::f32::consts::TAU / (k / m).sqrt();
+        assert!((period - expected).abs() < 2.0 * dt, "{} != {}", period, expected);
+        assert!((energy(&world) - 0.5 * k * 4.0).abs() < 1e-2);
+    }
+
+    #[test]
+    fn damping_removes_energy() {
+        let (mut world, _) = oscillator(40.0, 2.5, 1.0);
+        let start = energy(&world);
+        let mut previous = start;
+        for _ in 0..20 {
+            for _ in 0..50 {
+                world.step(0.001);
+            }
+            let current = energy(&world);
+            assert!(current < previous, "{} >= {}", current, previous);
+            previous = current;
+        }
+        // Energy decays roughly as exp(-c t / m)
+        assert!((previous / start - (-1.0f32 / 2.5).exp()).abs() < 0.05, "{}", previous / start);
+    }
+
+    #[test]
+    fn tension_is_positive_when_stretched() {
+        let (mut world, body) = oscillator(40.0, 2.5, 0.0);
+        let spring = world.springs()[0].clone();
+        assert!((spring.tension(world.bodies()) - 80.0).abs() < 1e-4);
+        assert!((spring.extension(world.bodies()) - 2.0).abs() < 1e-5);
+
+        world.body_mut(body).position = Vector2::new(7.0, 0.0);
+        assert!((spring.tension(world.bodies()) + 120.0).abs() < 1e-4);
+
+        // Pulling together means the force on `a` points towards `b`
+        world.body_mut(body).position = Vector2::new(12.0, 0.0);
+        world.step(0.001);
+        assert!(world.body(body).velocity.x < 0.0);
+    }
+}
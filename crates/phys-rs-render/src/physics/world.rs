@@ -0,0 +1,311 @@
+use crate::{Brush, math::Vector2};
+
+use super::{RigidBody, Integrator, SemiImplicitEuler, collision::{collide, Manifold}, broadphase::{Aabb, BroadPhase, BroadPhaseStats, SweepAndPrune}, contact::ContactSolver, joint::Joint, spring::Spring, field::FieldEntry};
+
+// Position (x, y, angle) and velocity entries per body in the integrator state
+const STATE_PER_BODY: usize = 3;
+
+// ====< HANDLES >====
+/// Stable reference to a body inside a `PhysicsWorld`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BodyHandle(pub(crate) usize);
+
+impl BodyHandle {
+    pub fn index(&self) -> usize {
+        self.0
+    }
+}
+
+// ====< COLLISION >====
+#[derive(Debug, Clone, PartialEq)]
+pub struct Collision {
+    pub a: BodyHandle,
+    pub b: BodyHandle,
+    // Normal points from `a` to `b`
+    pub manifold: Manifold,
+    // Total impulses the contact solver applied to `b`, `a` got the opposite
+    pub normal_impulse: f32,
+    pub tangent_impulse: f32,
+}
+
+// ====< WORLD >====
+/// Rigid bodies stepped together with the scene.
+/// Forces are applied from `PhysRenderable::apply_forces` and cleared after every step.
+pub struct PhysicsWorld {
+    bodies: Vec<RigidBody>,
+    time: f32,
+    // Integrator state, kept to avoid allocating every step
+    q: Vec<f32>,
+    v: Vec<f32>,
+
+    /// Only springs and fields are evaluated at the intermediate states of multi-stage integrators.
+    /// `RigidBody::force` and `torque` are held constant over the step, so with nothing but
+    /// accumulated forces RK4 and Verlet give the same motion as the Euler methods.
+    pub integrator: Box<dyn Integrator>,
+
+    pub(crate) springs: Vec<Spring>,
+    pub(crate) fields: Vec<FieldEntry>,
+    pub(crate) joints: Vec<Joint>,
+    // Passes over every joint per step, for positions and then for velocities
+    pub joint_iterations: usize,
+
+    // Found at the end of every step
+    collisions: Vec<Collision>,
+    pub detect_collisions: bool,
+    pub broad_phase: Box<dyn BroadPhase>,
+    broad_phase_stats: BroadPhaseStats,
+    // Bounces and friction, only with `detect_collisions`
+    pub resolve_collisions: bool,
+    pub contact_solver: ContactSolver,
+
+    // Draw every body before the scene objects
+    pub visible: bool,
+    pub show_contacts: bool,
+    pub show_fields: bool,
+    // Pixels per unit of force for the field overlay
+    pub field_arrow_scale: f32,
+}
+
+impl Default for PhysicsWorld {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PhysicsWorld {
+    pub fn new() -> Self {
+        Self {
+            bodies: Vec::new(),
+            time: 0.0,
+            q: Vec::new(),
+            v: Vec::new(),
+            integrator: Box::new(SemiImplicitEuler),
+            springs: Vec::new(),
+            fields: Vec::new(),
+            joints: Vec::new(),
+            joint_iterations: 10,
+            collisions: Vec::new(),
+            detect_collisions: true,
+            broad_phase: Box::new(SweepAndPrune::default()),
+            broad_phase_stats: BroadPhaseStats::default(),
+            resolve_collisions: true,
+            contact_solver: ContactSolver::default(),
+            visible: true,
+            show_contacts: false,
+            show_fields: false,
+            field_arrow_scale: 0.1,
+        }
+    }
+
+    pub fn add_body(&mut self, body: RigidBody) -> BodyHandle {
+        self.bodies.push(body);
+        BodyHandle(self.bodies.len() - 1)
+    }
+
+    pub fn body(&self, handle: BodyHandle) -> &RigidBody {
+        &self.bodies[handle.0]
+    }
+
+    pub fn body_mut(&mut self, handle: BodyHandle) -> &mut RigidBody {
+        &mut self.bodies[handle.0]
+    }
+
+    pub fn bodies(&self) -> &[RigidBody] {
+        &self.bodies
+    }
+
+    pub fn bodies_mut(&mut self) -> &mut [RigidBody] {
+        &mut self.bodies
+    }
+
+    pub fn handles(&self) -> impl Iterator<Item = BodyHandle> {
+        (0..self.bodies.len()).map(BodyHandle)
+    }
+
+    pub fn len(&self) -> usize {
+        self.bodies.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bodies.is_empty()
+    }
+
+    pub fn with_integrator(self, integrator: impl Integrator + 'static) -> Self {
+        Self { integrator: Box::new(integrator), ..self }
+    }
+
+    pub fn with_broad_phase(self, broad_phase: impl BroadPhase + 'static) -> Self {
+        Self { broad_phase: Box::new(broad_phase), ..self }
+    }
+
+    // Simulated time of the world
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    pub fn kinetic_energy(&self) -> f32 {
+        self.bodies.iter().map(|body| body.kinetic_energy()).sum()
+    }
+
+    // Moves every body by `dt` with the forces accumulated since the last step
+    pub fn step(&mut self, dt: f32) {
+        let (q, v) = (&mut self.q, &mut self.v);
+        q.clear();
+        v.clear();
+        for body in self.bodies.iter_mut() {
+            body.previous_position = body.position;
+            body.previous_angle = body.angle;
+
+            q.extend([body.position.x, body.position.y, body.angle]);
+            v.extend([body.velocity.x, body.velocity.y, body.angular_velocity]);
+        }
+
+        let (bodies, springs, fields) = (&self.bodies, &self.springs, &self.fields);
+        self.integrator.step(self.time, dt, q, v, &mut |t, q, v, a| {
+            accelerations(bodies, springs, fields, t, q, v, a);
+        });
+
+        for (i, body) in self.bodies.iter_mut().enumerate() {
+            if !body.is_static() {
+                let (q, v) = (&self.q[i * STATE_PER_BODY..], &self.v[i * STATE_PER_BODY..]);
+                body.position = Vector2::new(q[0], q[1]);
+                body.angle = q[2];
+                body.velocity = Vector2::new(v[0], v[1]);
+                body.angular_velocity = v[2];
+            }
+
+            body.clear_forces();
+        }
+        self.time += dt;
+
+        self.solve_joints();
+
+        self.collisions.clear();
+        if self.detect_collisions {
+            self.collisions = self.find_collisions();
+            if self.resolve_collisions {
+                self.contact_solver.solve(&mut self.bodies, &mut self.collisions);
+                self.contact_solver.correct_positions(&mut self.bodies, &self.collisions);
+            }
+        }
+    }
+
+    // Projects the positions back onto the constraints, then removes the velocities along them
+    fn solve_joints(&mut self) {
+        for _ in 0..self.joint_iterations {
+            for joint in self.joints.iter() {
+                joint.solve_position(&mut self.bodies);
+            }
+        }
+        for _ in 0..self.joint_iterations {
+            for joint in self.joints.iter() {
+                joint.solve_velocity(&mut self.bodies);
+            }
+        }
+    }
+
+    // Collisions from the last step
+    pub fn collisions(&self) -> &[Collision] {
+        &self.collisions
+    }
+
+    // Narrow phase on the pairs from the broad phase, static pairs are skipped
+    pub fn find_collisions(&mut self) -> Vec<Collision> {
+        self.update_broad_phase();
+        let start = std::time::Instant::now();
+        let pairs = self.broad_phase.pairs();
+        self.broad_phase_stats.time += start.elapsed().as_secs_f32();
+
+        let mut collisions = Vec::new();
+        for (i, j) in pairs {
+            let (a, b) = (&self.bodies[i], &self.bodies[j]);
+            if a.is_static() && b.is_static() {
+                continue;
+            }
+            self.broad_phase_stats.candidates += 1;
+            if let Some(manifold) = collide(a, b) {
+                collisions.push(Collision { a: BodyHandle(i), b: BodyHandle(j), manifold, normal_impulse: 0.0, tangent_impulse: 0.0 });
+            }
+        }
+        self.broad_phase_stats.collisions = collisions.len();
+        collisions
+    }
+
+    // Rebuilds the broad phase from the current body positions.
+    // Done by every step, call it after moving or adding bodies by hand to query them right away.
+    pub fn update_broad_phase(&mut self) {
+        let start = std::time::Instant::now();
+        let aabbs: Vec<Aabb> = self.bodies.iter().map(Aabb::from_body).collect();
+        self.broad_phase.update(&aabbs);
+        self.broad_phase_stats = BroadPhaseStats {
+            name: self.broad_phase.name(),
+            bodies: aabbs.len(),
+            time: start.elapsed().as_secs_f32(),
+            ..Default::default()
+        };
+    }
+
+    pub fn broad_phase_stats(&self) -> BroadPhaseStats {
+        self.broad_phase_stats
+    }
+
+    // Bodies whose bounding box overlaps `region`, as of the last broad phase update
+    pub fn query_region(&self, region: Aabb) -> Vec<BodyHandle> {
+        self.broad_phase.query_region(region).into_iter().map(BodyHandle).collect()
+    }
+
+    // Bodies under `point`, tested against their actual shape
+    pub fn query_point(&self, point: Vector2) -> Vec<BodyHandle> {
+        self.broad_phase.query_point(point).into_iter()
+            .filter(|i| self.bodies[*i].contains_point(point))
+            .map(BodyHandle)
+            .collect()
+    }
+
+    pub fn render(&self, brush: &mut Brush, alpha: f32) {
+        if !self.visible {
+            return;
+        }
+
+        for spring in self.springs.iter() {
+            spring.render(brush, &self.bodies, alpha);
+        }
+
+        for body in self.bodies.iter().filter(|body| body.visible) {
+            body.render(brush, alpha);
+        }
+
+        for joint in self.joints.iter() {
+            joint.render(brush, &self.bodies, alpha);
+        }
+
+        if self.show_contacts {
+            for collision in self.collisions.iter() {
+                collision.manifold.render(brush);
+            }
+        }
+
+        if self.show_fields {
+            self.render_fields(brush, alpha);
+        }
+    }
+}
+
+// Accelerations of every body for the integrator state `q` and `v`.
+// Accumulated forces are constant over the step, springs and fields follow the state.
+fn accelerations(bodies: &[RigidBody], springs: &[Spring], fields: &[FieldEntry], _t: f32, q: &[f32], v: &[f32], a: &mut [f32]) {
+    for (i, (body, a)) in bodies.iter().zip(a.chunks_mut(STATE_PER_BODY)).enumerate() {
+        let (q, v) = (&q[i * STATE_PER_BODY..], &v[i * STATE_PER_BODY..]);
+        let (position, velocity) = (Vector2::new(q[0], q[1]), Vector2::new(v[0], v[1]));
+        let field = fields.iter().fold(Vector2::zero(), |sum, field| sum + field.force_on(body, position, velocity));
+
+        let linear = (body.force + field) * body.inv_mass();
+        a[0] = linear.x;
+        a[1] = linear.y;
+        a[2] = body.torque * body.inv_inertia();
+    }
+
+    for spring in springs.iter() {
+        spring.accelerate(bodies, q, v, a, STATE_PER_BODY);
+    }
+}
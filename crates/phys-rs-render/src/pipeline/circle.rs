@@ -1,8 +1,10 @@
+use std::ops::Range;
+
 use bytemuck::{Pod, Zeroable};
 
 use crate::{renderer::Renderer, vec2::Vector2, color::Color, create_pipeline, render_pass, write_buffer};
 
-use super::PhysPipeline;
+use super::{PhysPipeline, BatchedPipeline};
 
 const MAX_DEFAULT_CIRCLES: usize = 100;
 const INDICES: &[u16] = &[0, 1, 2, 0, 2, 3];
@@ -84,8 +86,29 @@ impl PhysPipeline for CirclePipeline {
             return;
         }
 
+        self.prepare(renderer, encoder);
+        self.draw_range(renderer, encoder, view, 0..self.circles.len());
+    }
+}
+
+impl BatchedPipeline for CirclePipeline {
+    fn item_count(&self) -> usize {
+        self.circles.len()
+    }
+
+    fn prepare(&mut self, renderer: &mut Renderer, encoder: &mut wgpu::CommandEncoder) {
+        if self.circles.is_empty() {
+            return;
+        }
+
         let circles = &self.circles;
         write_buffer!(circles, self, renderer, encoder);
+    }
+
+    fn draw_range(&mut self, renderer: &mut Renderer, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView, range: Range<usize>) {
+        if range.is_empty() {
+            return;
+        }
 
         let mut render_pass = render_pass!(encoder, view);
 
@@ -93,7 +116,7 @@ impl PhysPipeline for CirclePipeline {
         render_pass.set_bind_group(0, &renderer.globals_bind_group, &[]);
         render_pass.set_vertex_buffer(0, self.instances.slice(..));
         render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
-        render_pass.draw_indexed(0..INDICES.len() as u32, 0, 0..circles.len() as u32);
+        render_pass.draw_indexed(0..INDICES.len() as u32, 0, range.start as u32..range.end as u32);
 
         renderer.draw_calls += 1;
     }
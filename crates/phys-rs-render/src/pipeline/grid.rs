@@ -78,6 +78,14 @@ impl GridPipeline {
     pub fn set_grids(&mut self, grids: Vec<Grid>) {
         self.grids = Some(grids);
     }
+
+    pub fn grids(&self) -> &[Grid] {
+        self.grids.as_deref().unwrap_or(&[])
+    }
+
+    pub fn clear(&mut self) {
+        self.grids = None;
+    }
 }
 
 impl PhysPipeline for GridPipeline {
@@ -94,7 +102,7 @@ impl PhysPipeline for GridPipeline {
     }
 
     fn execute(&mut self, renderer: &mut crate::renderer::Renderer, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView) {
-        if self.grids.is_none() {
+        if self.grids().is_empty() {
             return;
         }
 
@@ -1,23 +1,30 @@
+use std::ops::Range;
+
 use crate::renderer::Renderer;
 
 mod grid;
 mod circle;
 mod poly;
 mod quad;
+mod text;
 
 pub mod pipelines {
     pub use super::grid::GridPipeline;
     pub use super::circle::CirclePipeline;
     pub use super::poly::PolyPipeline;
     pub use super::quad::QuadPipeline;
+    pub use super::text::TextPipeline;
 }
 
 pub mod elements {
     pub use super::grid::Grid;
     pub use super::circle::Circle;
+    // Not used inside the crate yet
+    #[allow(unused_imports)]
     pub use super::poly::Vertex;
     pub use super::poly::Primitive;
     pub use super::quad::Quad;
+    pub use super::text::{Text, TextAnchor};
 }
 
 pub trait PhysPipeline {
@@ -25,6 +32,37 @@ pub trait PhysPipeline {
     fn create(renderer: &mut Renderer) -> Self;
 }
 
+// Pipelines that can draw their items in several separate ranges,
+// which lets the brush interleave draws from different pipelines.
+pub trait BatchedPipeline {
+    fn item_count(&self) -> usize;
+    // Uploads every item, called once before any `draw_range`
+    fn prepare(&mut self, renderer: &mut Renderer, encoder: &mut wgpu::CommandEncoder);
+    fn draw_range(&mut self, renderer: &mut Renderer, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView, range: Range<usize>);
+}
+
+// ====< BUFFERS >====
+// Reallocates `buffer`, doubling its size until `required` bytes fit.
+// Returns true if the buffer was replaced, so bind groups using it have to be recreated.
+pub fn grow_buffer(renderer: &Renderer, buffer: &mut wgpu::Buffer, required: u64, label: &str) -> bool {
+    if required <= buffer.size() {
+        return false;
+    }
+
+    let mut size = buffer.size().max(wgpu::COPY_BUFFER_ALIGNMENT);
+    while size < required {
+        size *= 2;
+    }
+
+    *buffer = renderer.device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some(label),
+        size,
+        usage: buffer.usage(),
+        mapped_at_creation: false,
+    });
+    true
+}
+
 // ====< MACROS >====
 #[macro_export]
 macro_rules! create_pipeline {
@@ -77,7 +115,8 @@ macro_rules! create_pipeline {
                 topology: wgpu::PrimitiveTopology::TriangleList,
                 strip_index_format: None,
                 front_face: wgpu::FrontFace::Ccw,
-                cull_mode: Some(wgpu::Face::Back),
+                // A y-up `Units` mirrors every triangle, so both windings have to be drawn
+                cull_mode: None,
                 polygon_mode: wgpu::PolygonMode::Fill,
                 unclipped_depth: false,
                 conservative: false,
@@ -95,6 +134,7 @@ macro_rules! create_pipeline {
 macro_rules! write_buffer {
     ($inst:expr, $self:ident, $renderer:ident, $encoder:ident, $buffer:expr) => {{
         let instance_bytes: &[u8] = bytemuck::cast_slice($inst.as_slice());
+        let grown = $crate::pipeline::grow_buffer($renderer, &mut $buffer, instance_bytes.len() as u64, stringify!($buffer));
         let mut instance_buffer = $renderer.staging_belt.write_buffer(
             $encoder,
             &$buffer,
@@ -103,6 +143,7 @@ macro_rules! write_buffer {
             &$renderer.device);
 
         instance_buffer.copy_from_slice(instance_bytes);
+        grown
     }};
 
     ($inst:expr, $self:ident, $renderer:ident, $encoder:ident) => {{
@@ -1,12 +1,14 @@
+use std::ops::Range;
+
 use bytemuck::{Zeroable, Pod};
 use lyon::{path::{Path, path::Builder}, lyon_tessellation::{FillOptions, VertexBuffers, BuffersBuilder}};
 
 use crate::{write_buffer, render_pass};
 
-use super::PhysPipeline;
+use super::{PhysPipeline, BatchedPipeline};
 
 const MAX_DEFAULT_VERTICES: usize = 1000;
-const MAX_DEFAULT_PRIMITIVES: usize = 2;
+const MAX_DEFAULT_PRIMITIVES: usize = 64;
 
 
 #[repr(C)]
@@ -19,20 +21,21 @@ pub struct Vertex {
 #[repr(C)]
 #[derive(Copy, Clone, Debug, Pod, Zeroable)]
 pub struct Primitive {
+    // Field order matches the WGSL struct layout
     pub color: [f32; 4],
-    pub angle: f32,
     pub origin: [f32; 2],
+    pub angle: f32,
 
-    pub indices_count: u32,
+    _padding: u32,
 }
 
 impl Default for Primitive {
     fn default() -> Self {
         Self {
             color: [0.0, 0.0, 0.0, 0.0],
-            angle: 0.0,
             origin: [0.0, 0.0],
-            indices_count: 0,
+            angle: 0.0,
+            _padding: 0,
         }
     }
 }
@@ -71,6 +74,17 @@ impl Vertex {
     }
 }
 
+fn create_primitives_bind_group(renderer: &crate::Renderer, layout: &wgpu::BindGroupLayout, buffer: &wgpu::Buffer) -> wgpu::BindGroup {
+    renderer.device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Poly primitives bind group"),
+        layout,
+        entries: &[wgpu::BindGroupEntry {
+            binding: 0,
+            resource: buffer.as_entire_binding(),
+        }],
+    })
+}
+
 pub struct PolyPipeline {
     pub pipeline: wgpu::RenderPipeline,
     pub vbo: wgpu::Buffer,
@@ -81,17 +95,18 @@ pub struct PolyPipeline {
     pub fill_tess: lyon::tessellation::FillTessellator,
     pub stroke_tess: lyon::tessellation::StrokeTessellator,
 
-    pub geometry: VertexBuffers<Vertex, u16>,
+    pub geometry: VertexBuffers<Vertex, u32>,
 
-    // uniforms
+    // storage
     pub primitives_buffer: wgpu::Buffer,
     pub primitives_bind_group: wgpu::BindGroup,
     pub primitives_bind_group_layout: wgpu::BindGroupLayout,
 
     // primitives
     pub primitives: Vec<Primitive>,
+    // Index count at the end of every primitive
+    primitive_ends: Vec<u32>,
     i_index: usize,
-    v_count: u32,
 }
 
 impl PolyPipeline {
@@ -101,9 +116,8 @@ impl PolyPipeline {
         self.tesselate(&builder_l.build(), primitive);
     }
 
+    // Geometry tesselated without a primitive belongs to the next one that is given
     pub fn tesselate(&mut self, path: &Path, primitive: Option<Primitive>) {
-
-        let size_before = self.geometry.indices.len();
         self.fill_tess.tessellate_path(
             path,
             &FillOptions::default(),
@@ -115,12 +129,10 @@ impl PolyPipeline {
             })
         ).unwrap();
 
-        self.v_count += (self.geometry.indices.len() - size_before) as u32;
-
-        if let Some(mut primitive) = primitive {
-            primitive.indices_count = self.v_count;
-            self.primitives.push(primitive); 
-            self.v_count = 0;
+        if let Some(primitive) = primitive {
+            self.primitives.push(primitive);
+            self.index_count = self.geometry.indices.len() as u32;
+            self.primitive_ends.push(self.index_count);
             self.i_index += 1;
         }
     }
@@ -129,59 +141,22 @@ impl PolyPipeline {
         self.geometry.indices.clear();
         self.geometry.vertices.clear();
         self.primitives.clear();
+        self.primitive_ends.clear();
 
-        self.v_count = 0;
+        self.index_count = 0;
         self.i_index = 0;
     }
 }
 
 impl PhysPipeline for PolyPipeline {
     fn execute(&mut self, renderer: &mut crate::Renderer, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView) {
-        if self.geometry.vertices.is_empty() {
+        // Only geometry that already has its primitive is drawn
+        if self.index_count == 0 {
             return;
         }
 
-        // Copy buffers
-        write_buffer!(self.geometry.vertices, self, renderer, encoder, self.vbo);
-        write_buffer!(self.geometry.indices, self, renderer, encoder, self.ibo);
-        write_buffer!(self.primitives, self, renderer, encoder, self.primitives_buffer);
-        // let mut primitive_buffer = renderer.staging_belt.write_buffer(
-        //     encoder,
-        //     &self.primitives_buffer,
-        //     0,
-        //     wgpu::BufferSize::new(self.primitives_buffer.size()).unwrap(),
-        //     &renderer.device);
-
-        // primitive_buffer.copy_from_slice(primitive_bytes);
-
-
-        // Draw
-        {
-            let mut index_offset = 0;
-            let mut render_pass = render_pass!(encoder, view);
-
-
-            render_pass.set_pipeline(&self.pipeline);
-            render_pass.set_bind_group(0, &renderer.globals_bind_group, &[]);
-            render_pass.set_bind_group(1, &self.primitives_bind_group, &[]);
-            render_pass.set_vertex_buffer(0, self.vbo.slice(..));
-            render_pass.set_index_buffer(self.ibo.slice(..), wgpu::IndexFormat::Uint16);
-
-
-            let mut i = 0;
-            for primitive in self.primitives.iter() {
-                let offset = i;
-
-                render_pass.draw_indexed(index_offset..index_offset+primitive.indices_count as u32, 0, 0..1);
-                renderer.draw_calls += 1;
-
-                index_offset += primitive.indices_count;
-                i += 1;
-            }
-        }
-
-        self.clear();
-        // ::std::process::exit(0);
+        self.prepare(renderer, encoder);
+        self.draw_range(renderer, encoder, view, 0..self.primitives.len());
     }
 
     fn create(renderer: &mut crate::Renderer) -> Self {
@@ -204,16 +179,16 @@ impl PhysPipeline for PolyPipeline {
 
         let ibo = renderer.device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("Poly index buffer"),
-            size: (MAX_DEFAULT_VERTICES * std::mem::size_of::<u16>()) as u64,
+            size: (MAX_DEFAULT_VERTICES * std::mem::size_of::<u32>()) as u64,
             usage: wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST,
             mapped_at_creation: false,
         });
 
-        // Create primitives uniform buffer
+        // Create primitives storage buffer
         let primitives_buffer = renderer.device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("Poly primitives buffer"),
             size: (MAX_DEFAULT_PRIMITIVES * std::mem::size_of::<Primitive>()) as u64,
-            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
             mapped_at_creation: false,
         });
 
@@ -224,23 +199,16 @@ impl PhysPipeline for PolyPipeline {
                 binding: 0,
                 visibility: wgpu::ShaderStages::VERTEX,
                 ty: wgpu::BindingType::Buffer {
-                    ty: wgpu::BufferBindingType::Uniform,
+                    ty: wgpu::BufferBindingType::Storage { read_only: true },
                     has_dynamic_offset: false,
-                    min_binding_size: wgpu::BufferSize::new((MAX_DEFAULT_PRIMITIVES * std::mem::size_of::<Primitive>()) as u64),
+                    min_binding_size: wgpu::BufferSize::new(std::mem::size_of::<Primitive>() as u64),
                 },
                 count: None,
             }],
         });
 
         // Create primitives bind group
-        let primitives_bind_group = renderer.device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Poly primitives bind group"),
-            layout: &primitives_bind_group_layout,
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: primitives_buffer.as_entire_binding(),
-            }],
-        });
+        let primitives_bind_group = create_primitives_bind_group(renderer, &primitives_bind_group_layout, &primitives_buffer);
 
         // Create render pipeline layout
         let render_pipeline_layout = renderer.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
@@ -251,7 +219,7 @@ impl PhysPipeline for PolyPipeline {
 
         // Create pipeline
         let pipeline = renderer.device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some(concat!(stringify!($id), " pipeline")),
+            label: Some("Poly pipeline"),
             layout: Some(&render_pipeline_layout),
             vertex: wgpu::VertexState {
                 module: &shader,
@@ -271,7 +239,8 @@ impl PhysPipeline for PolyPipeline {
                 topology: wgpu::PrimitiveTopology::TriangleList,
                 strip_index_format: None,
                 front_face: wgpu::FrontFace::Ccw,
-                cull_mode: Some(wgpu::Face::Back),
+                // A y-up `Units` mirrors every triangle, so both windings have to be drawn
+                cull_mode: None,
                 polygon_mode: wgpu::PolygonMode::Fill,
                 unclipped_depth: false,
                 conservative: false,
@@ -295,9 +264,49 @@ impl PhysPipeline for PolyPipeline {
 
             geometry: VertexBuffers::new(),
             primitives: Vec::with_capacity(MAX_DEFAULT_PRIMITIVES),
+            primitive_ends: Vec::with_capacity(MAX_DEFAULT_PRIMITIVES),
 
-            v_count: 0,
             i_index: 0
         }
     }
-}
\ No newline at end of file
+}
+
+impl BatchedPipeline for PolyPipeline {
+    fn item_count(&self) -> usize {
+        self.primitives.len()
+    }
+
+    fn prepare(&mut self, renderer: &mut crate::Renderer, encoder: &mut wgpu::CommandEncoder) {
+        if self.index_count == 0 {
+            return;
+        }
+
+        // Copy buffers
+        write_buffer!(self.geometry.vertices, self, renderer, encoder, self.vbo);
+        write_buffer!(self.geometry.indices, self, renderer, encoder, self.ibo);
+        if write_buffer!(self.primitives, self, renderer, encoder, self.primitives_buffer) {
+            self.primitives_bind_group = create_primitives_bind_group(renderer, &self.primitives_bind_group_layout, &self.primitives_buffer);
+        }
+    }
+
+    fn draw_range(&mut self, renderer: &mut crate::Renderer, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView, range: Range<usize>) {
+        if range.is_empty() {
+            return;
+        }
+
+        // Primitives are stored back to back, so a range of them is a single range of indices
+        let first_index = if range.start == 0 { 0 } else { self.primitive_ends[range.start - 1] };
+        let last_index = self.primitive_ends[range.end - 1];
+
+        let mut render_pass = render_pass!(encoder, view);
+
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &renderer.globals_bind_group, &[]);
+        render_pass.set_bind_group(1, &self.primitives_bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.vbo.slice(..));
+        render_pass.set_index_buffer(self.ibo.slice(..), wgpu::IndexFormat::Uint32);
+        render_pass.draw_indexed(first_index..last_index, 0, 0..1);
+
+        renderer.draw_calls += 1;
+    }
+}
@@ -1,8 +1,10 @@
+use std::ops::Range;
+
 use bytemuck::{Pod, Zeroable};
 
 use crate::{renderer::Renderer, vec2::Vector2, color::Color, create_pipeline, render_pass, write_buffer};
 
-use super::PhysPipeline;
+use super::{PhysPipeline, BatchedPipeline};
 
 const MAX_DEFAULT_QUADS: usize = 100;
 const INDICES: &[u16] = &[0, 1, 2, 0, 2, 3];
@@ -94,8 +96,29 @@ impl PhysPipeline for QuadPipeline {
             return;
         }
 
+        self.prepare(renderer, encoder);
+        self.draw_range(renderer, encoder, view, 0..self.quads.len());
+    }
+}
+
+impl BatchedPipeline for QuadPipeline {
+    fn item_count(&self) -> usize {
+        self.quads.len()
+    }
+
+    fn prepare(&mut self, renderer: &mut Renderer, encoder: &mut wgpu::CommandEncoder) {
+        if self.quads.is_empty() {
+            return;
+        }
+
         let quads = &self.quads;
         write_buffer!(quads, self, renderer, encoder);
+    }
+
+    fn draw_range(&mut self, renderer: &mut Renderer, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView, range: Range<usize>) {
+        if range.is_empty() {
+            return;
+        }
 
         let mut render_pass = render_pass!(encoder, view);
 
@@ -103,7 +126,7 @@ impl PhysPipeline for QuadPipeline {
         render_pass.set_bind_group(0, &renderer.globals_bind_group, &[]);
         render_pass.set_vertex_buffer(0, self.instances.slice(..));
         render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
-        render_pass.draw_indexed(0..INDICES.len() as u32, 0, 0..quads.len() as u32);
+        render_pass.draw_indexed(0..INDICES.len() as u32, 0, range.start as u32..range.end as u32);
 
         renderer.draw_calls += 1;
     }
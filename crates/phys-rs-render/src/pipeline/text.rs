@@ -0,0 +1,197 @@
+use std::ops::Range;
+
+use wgpu_glyph::{ab_glyph::{FontArc, InvalidFont}, GlyphBrush, GlyphBrushBuilder, Section, Layout, HorizontalAlign, VerticalAlign, FontId};
+
+use crate::{renderer::Renderer, vec2::Vector2, color::Color, units::Units};
+
+use super::{PhysPipeline, BatchedPipeline};
+
+// Shipped with egui, so we do not need to bundle our own
+const DEFAULT_FONT: &str = "Ubuntu-Light";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TextAnchor {
+    #[default]
+    TopLeft,
+    TopCenter,
+    TopRight,
+    CenterLeft,
+    Center,
+    CenterRight,
+    BottomLeft,
+    BottomCenter,
+    BottomRight,
+}
+
+impl TextAnchor {
+    fn layout(&self) -> Layout<wgpu_glyph::BuiltInLineBreaker> {
+        let (h, v) = match self {
+            TextAnchor::TopLeft => (HorizontalAlign::Left, VerticalAlign::Top),
+            TextAnchor::TopCenter => (HorizontalAlign::Center, VerticalAlign::Top),
+            TextAnchor::TopRight => (HorizontalAlign::Right, VerticalAlign::Top),
+            TextAnchor::CenterLeft => (HorizontalAlign::Left, VerticalAlign::Center),
+            TextAnchor::Center => (HorizontalAlign::Center, VerticalAlign::Center),
+            TextAnchor::CenterRight => (HorizontalAlign::Right, VerticalAlign::Center),
+            TextAnchor::BottomLeft => (HorizontalAlign::Left, VerticalAlign::Bottom),
+            TextAnchor::BottomCenter => (HorizontalAlign::Center, VerticalAlign::Bottom),
+            TextAnchor::BottomRight => (HorizontalAlign::Right, VerticalAlign::Bottom),
+        };
+        Layout::default_wrap().h_align(h).v_align(v)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Text {
+    pub text: String,
+    pub position: Vector2,
+    pub size: f32,
+    pub color: [f32; 4],
+    pub anchor: TextAnchor,
+    // Counter-clockwise, around `position`
+    pub rotation: f32,
+    pub font: FontId,
+}
+
+impl Text {
+    pub fn create(text: &str, position: Vector2, size: f32, color: Color, anchor: TextAnchor, rotation: f32) -> Self {
+        Self {
+            text: text.to_string(),
+            position,
+            size,
+            color: color.into(),
+            anchor,
+            rotation,
+            font: FontId::default(),
+        }
+    }
+
+    pub fn with_font(self, font: FontId) -> Self {
+        Self { font, ..self }
+    }
+
+    // Glyphs are laid out in pixels (y down), before the camera is applied
+    fn section(&self, units: &Units) -> Section<'_> {
+        let position = units.to_pixels(self.position);
+        Section::default()
+            .add_text(wgpu_glyph::Text::new(&self.text)
+                .with_scale(units.length_to_pixels(self.size))
+                .with_color(self.color)
+                .with_font_id(self.font))
+            .with_screen_position((position.x, position.y))
+            .with_layout(self.anchor.layout())
+    }
+}
+
+// ====< MATRICES >====
+// Column major, as expected by wgpu_glyph
+fn mul_mat4(a: [f32; 16], b: [f32; 16]) -> [f32; 16] {
+    let mut out = [0.0; 16];
+    for col in 0..4 {
+        for row in 0..4 {
+            out[col * 4 + row] = (0..4).map(|k| a[k * 4 + row] * b[col * 4 + k]).sum();
+        }
+    }
+    out
+}
+
+// Rotation around `origin` in pixels (y down), positive angle is counter-clockwise on screen
+fn rotation_around(origin: Vector2, angle: f32) -> [f32; 16] {
+    let (sin, cos) = angle.sin_cos();
+    let (a, b, c, d) = (cos, -sin, sin, cos);
+    let e = origin.x - (a * origin.x + c * origin.y);
+    let f = origin.y - (b * origin.x + d * origin.y);
+    [
+        a, b, 0.0, 0.0,
+        c, d, 0.0, 0.0,
+        0.0, 0.0, 1.0, 0.0,
+        e, f, 0.0, 1.0,
+    ]
+}
+
+pub struct TextPipeline {
+    glyph_brush: GlyphBrush<()>,
+
+    texts: Vec<Text>,
+}
+
+impl TextPipeline {
+    pub fn set_texts(&mut self, texts: Vec<Text>) {
+        self.texts = texts;
+    }
+
+    pub fn add_text(&mut self, text: Text) {
+        self.texts.push(text);
+    }
+
+    pub fn clear(&mut self) {
+        self.texts.clear();
+    }
+
+    pub fn load_font(&mut self, data: Vec<u8>) -> Result<FontId, InvalidFont> {
+        let font = FontArc::try_from_vec(data)?;
+        Ok(self.glyph_brush.add_font(font))
+    }
+}
+
+impl PhysPipeline for TextPipeline {
+    fn create(renderer: &mut Renderer) -> Self {
+        let fonts = egui::FontDefinitions::default();
+        let font_data = fonts.font_data.get(DEFAULT_FONT).expect("egui ships with the default font");
+        let font = FontArc::try_from_vec(font_data.font.to_vec()).expect("default font is valid");
+
+        let glyph_brush = GlyphBrushBuilder::using_font(font).build(&renderer.device, renderer.format);
+
+        Self {
+            glyph_brush,
+            texts: Vec::new(),
+        }
+    }
+
+    fn execute(&mut self, renderer: &mut Renderer, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView) {
+        self.draw_range(renderer, encoder, view, 0..self.texts.len());
+    }
+}
+
+impl BatchedPipeline for TextPipeline {
+    fn item_count(&self) -> usize {
+        self.texts.len()
+    }
+
+    // Glyphs are uploaded by wgpu_glyph when they are drawn
+    fn prepare(&mut self, _renderer: &mut Renderer, _encoder: &mut wgpu::CommandEncoder) {}
+
+    fn draw_range(&mut self, renderer: &mut Renderer, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView, range: Range<usize>) {
+        if range.is_empty() {
+            return;
+        }
+
+        let (width, height) = renderer.get_window_size();
+        let projection = mul_mat4(
+            wgpu_glyph::orthographic_projection(width, height),
+            renderer.camera.matrix(renderer.resolution()),
+        );
+        let texts = &self.texts[range];
+        let units = renderer.units;
+
+        // Everything that is not rotated goes in a single draw
+        let mut has_straight = false;
+        for text in texts.iter().filter(|text| text.rotation == 0.0) {
+            self.glyph_brush.queue(text.section(&units));
+            has_straight = true;
+        }
+        if has_straight {
+            self.glyph_brush.draw_queued_with_transform(&renderer.device, &mut renderer.staging_belt, encoder, view, projection)
+                .expect("draw queued text");
+            renderer.draw_calls += 1;
+        }
+
+        // Rotated text needs its own transform
+        for text in texts.iter().filter(|text| text.rotation != 0.0) {
+            self.glyph_brush.queue(text.section(&units));
+            let transform = mul_mat4(projection, rotation_around(units.to_pixels(text.position), text.rotation));
+            self.glyph_brush.draw_queued_with_transform(&renderer.device, &mut renderer.staging_belt, encoder, view, transform)
+                .expect("draw queued text");
+            renderer.draw_calls += 1;
+        }
+    }
+}
@@ -1,16 +1,54 @@
-use bytemuck::{Zeroable, Pod};
 use egui::FontDefinitions;
+use lyon::{geom::Box2D, path::path::Builder};
 use egui_wgpu_backend::RenderPass;
-use lyon::{geom::Box2D, path::builder::BorderRadii, math::Vector};
+use std::ops::Range;
+
+use bytemuck::{Zeroable, Pod};
 use wgpu::util::DeviceExt;
 use winit::window::Window;
+use wgpu_glyph::{FontId, ab_glyph::InvalidFont};
 
-use crate::{Scene, pipeline::{pipelines::{GridPipeline, CirclePipeline, PolyPipeline, QuadPipeline}, PhysPipeline, elements::{Grid, Circle, Primitive, Quad}}, color::{StandardColorPalette, Color}, vec2::Vector2, PhysApp};
+use crate::{Scene, DataCollector, pipeline::{pipelines::{GridPipeline, CirclePipeline, PolyPipeline, QuadPipeline, TextPipeline}, PhysPipeline, BatchedPipeline, elements::{Circle, Primitive, Quad, Text, TextAnchor}}, color::{StandardColorPalette, Color}, vec2::Vector2, camera::Camera2D, units::Units};
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug, Pod, Zeroable)]
 pub struct Globals {
     u_resolution: [f32; 2],
+    u_camera_position: [f32; 2],
+    u_camera_zoom: f32,
+    u_camera_rotation: f32,
+    u_metres_per_pixel: f32,
+    u_y_sign: f32,
+    u_origin: [f32; 2],
+
+    _padding: [f32; 2],
+}
+
+impl Globals {
+    fn set_camera(&mut self, camera: &Camera2D) {
+        self.u_camera_position = camera.position.into();
+        self.u_camera_zoom = camera.zoom;
+        self.u_camera_rotation = camera.rotation;
+    }
+
+    fn set_units(&mut self, units: &Units) {
+        self.u_metres_per_pixel = units.metres_per_pixel;
+        self.u_y_sign = units.y_sign();
+        self.u_origin = units.origin.into();
+    }
+}
+
+// Plain numbers for the UI, so the renderer does not depend on the physics types
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CollisionCounters {
+    pub broad_phase: &'static str,
+    pub bodies: usize,
+    pub candidate_pairs: usize,
+    // Pairs a brute force test would check
+    pub all_pairs: usize,
+    pub collisions: usize,
+    // Seconds spent in the broad phase
+    pub broad_phase_time: f32,
 }
 
 pub struct Renderer {
@@ -33,6 +71,10 @@ pub struct Renderer {
 
     pub brush: Option<Brush>,
 
+    // Copied from the scene at the start of every frame
+    pub camera: Camera2D,
+    pub units: Units,
+
     has_to_update_globals: bool,
 
     pub encoder: Option<wgpu::CommandEncoder>,
@@ -43,6 +85,8 @@ pub struct Renderer {
     pub ldt: f32, // Last delta time
 
     pub avg_update_time: f32,
+    // Filled by the app after every update
+    pub collision_counters: CollisionCounters,
 }
 
 impl Renderer {
@@ -89,9 +133,14 @@ impl Renderer {
         let staging_belt = wgpu::util::StagingBelt::new(10 * 1024);
 
         // Globals
-        let globals = Globals {
+        let camera = Camera2D::default();
+        let units = Units::default();
+        let mut globals = Globals {
             u_resolution: [window.inner_size().width as f32, window.inner_size().height as f32],
+            ..Zeroable::zeroed()
         };
+        globals.set_camera(&camera);
+        globals.set_units(&units);
 
         // Create globals uniform buffer
         let globals_uniform = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
@@ -144,6 +193,8 @@ impl Renderer {
             surface_config,
 
             brush: None,
+            camera,
+            units,
             has_to_update_globals: false,
 
             encoder: None,
@@ -153,6 +204,7 @@ impl Renderer {
             draw_calls: 0,
             ldt: 0.0,
             avg_update_time: 0.0,
+            collision_counters: CollisionCounters::default(),
         };
 
         // Brush
@@ -185,6 +237,35 @@ impl Renderer {
         globals_buffer.copy_from_slice(global_bytes);
     }
 
+    pub fn set_camera(&mut self, camera: Camera2D) {
+        if camera != self.camera {
+            self.camera = camera;
+            self.globals.set_camera(&camera);
+            self.has_to_update_globals = true;
+        }
+    }
+
+    pub fn set_units(&mut self, units: Units) {
+        if units != self.units {
+            self.units = units;
+            self.globals.set_units(&units);
+            self.has_to_update_globals = true;
+        }
+    }
+
+    pub fn resolution(&self) -> Vector2 {
+        let (width, height) = self.get_window_size();
+        Vector2::new(width as f32, height as f32)
+    }
+
+    pub fn world_to_screen(&self, point: Vector2) -> Vector2 {
+        self.camera.world_to_screen(self.units.to_pixels(point), self.resolution())
+    }
+
+    pub fn screen_to_world(&self, point: Vector2) -> Vector2 {
+        self.units.from_pixels(self.camera.screen_to_world(point, self.resolution()))
+    }
+
     pub fn handle_event(&mut self, event: &winit::event::Event<()>) {
         self.platform.handle_event(event);
     }
@@ -197,6 +278,8 @@ impl Renderer {
             label: Some("Render Encoder"),
         });
 
+        self.set_camera(scene.camera);
+        self.set_units(scene.units);
         if self.has_to_update_globals {
             self.update_uniforms(&mut encoder);
             self.has_to_update_globals = false;
@@ -220,6 +303,11 @@ impl Renderer {
         // Draw pipelines
         if self.brush.is_some() {
             let mut pipelines = self.brush.take().unwrap();
+            pipelines.units = scene.units;
+            match scene.grid {
+                Some(grid) => pipelines.grid_pipeline.set_grids(vec![grid]),
+                None => pipelines.grid_pipeline.clear(),
+            }
             pipelines.execute_once(self, &mut encoder, &view);
             self.brush = Some(pipelines);
         }
@@ -244,7 +332,7 @@ impl Renderer {
         self.view = Some(view);
     }
 
-    pub fn render_end(&mut self, scene: &mut Scene) {
+    pub fn render_end(&mut self, scene: &mut Scene, data_collector: &DataCollector) {
         if let(Some(mut encoder), Some(view), Some(frame)) = (self.encoder.take(), self.view.take(), self.frame.take()) {
             // Clear pipelines
             if self.brush.is_some() {
@@ -255,8 +343,8 @@ impl Renderer {
             }
 
             // draw UI
-            if scene.ui.is_some() {
-                scene.ui.as_mut().unwrap().ui(&self.platform.context(), self);
+            if let Some(ui) = scene.ui.as_mut() {
+                ui.ui(&self.platform.context(), self, data_collector);
             }
             // Finish drawing egui
             let screen_descriptor = egui_wgpu_backend::ScreenDescriptor {
@@ -302,35 +390,78 @@ impl Renderer {
 
 
 // ====< BRUSH >====
+const ARROW_HEAD_ANGLE: f32 = 0.5; // about 30 degrees
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BatchKind {
+    Circle,
+    Polygon,
+    Quad,
+    Text,
+}
+
+// Consecutive items of one pipeline drawn on the same layer
+#[derive(Debug, Clone)]
+struct Batch {
+    kind: BatchKind,
+    layer: i32,
+    range: Range<usize>,
+}
+
 pub struct Brush {
     // pipelines
     pub grid_pipeline: GridPipeline,
     pub circle_pipeline: CirclePipeline,
     pub polygon_pipeline: PolyPipeline,
     pub quad_pipeline: QuadPipeline,
+    pub text_pipeline: TextPipeline,
+
+    // draw order
+    layer: i32,
+    batches: Vec<Batch>,
+
+    // Units of the scene being drawn
+    pub units: Units,
 }
 
 impl Brush {
     pub fn new(renderer: &mut Renderer) -> Self {
-        let mut grid = GridPipeline::create(renderer);
-        grid.set_grids(vec![
-            Grid::fullscreen(StandardColorPalette::GRID, 30.0, 1.0, 5)
-        ]);
         Self {
-            grid_pipeline: grid,
+            grid_pipeline: GridPipeline::create(renderer),
             circle_pipeline: CirclePipeline::create(renderer),
             polygon_pipeline: PolyPipeline::create(renderer),
             quad_pipeline: QuadPipeline::create(renderer),
+            text_pipeline: TextPipeline::create(renderer),
+
+            layer: 0,
+            batches: Vec::new(),
+
+            units: Units::default(),
         }
     }
 
     pub fn execute_once(&mut self, renderer: &mut Renderer, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView) {
         self.grid_pipeline.execute(renderer, encoder, view);
     }
+    // Draws lower layers first, and in submission order within a layer
     pub fn execute(&mut self, renderer: &mut Renderer, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView) {
-        self.circle_pipeline.execute(renderer, encoder, view);
-        self.polygon_pipeline.execute(renderer, encoder, view);
-        self.quad_pipeline.execute(renderer, encoder, view);
+        self.track_untracked();
+
+        self.circle_pipeline.prepare(renderer, encoder);
+        self.polygon_pipeline.prepare(renderer, encoder);
+        self.quad_pipeline.prepare(renderer, encoder);
+        self.text_pipeline.prepare(renderer, encoder);
+
+        let mut batches = std::mem::take(&mut self.batches);
+        batches.sort_by_key(|batch| batch.layer);
+        for batch in batches {
+            match batch.kind {
+                BatchKind::Circle => self.circle_pipeline.draw_range(renderer, encoder, view, batch.range),
+                BatchKind::Polygon => self.polygon_pipeline.draw_range(renderer, encoder, view, batch.range),
+                BatchKind::Quad => self.quad_pipeline.draw_range(renderer, encoder, view, batch.range),
+                BatchKind::Text => self.text_pipeline.draw_range(renderer, encoder, view, batch.range),
+            }
+        }
     }
 
     // ====< BASIC >====
@@ -338,15 +469,83 @@ impl Brush {
         self.circle_pipeline.clear();
         self.polygon_pipeline.clear();
         self.quad_pipeline.clear();
+        self.text_pipeline.clear();
+
+        self.batches.clear();
+        self.layer = 0;
+    }
+
+    // World length of `pixels` pixels, for sizes that should not scale with the units
+    pub fn px(&self, pixels: f32) -> f32 {
+        self.units.length_from_pixels(pixels)
+    }
+
+    // ====< LAYERS >====
+    // Everything drawn after this call goes on `layer`, higher layers are drawn on top
+    pub fn set_layer(&mut self, layer: i32) {
+        self.layer = layer;
+    }
+
+    pub fn layer(&self) -> i32 {
+        self.layer
+    }
+
+    pub fn with_layer(&mut self, layer: i32, draw: impl FnOnce(&mut Self)) {
+        let previous = self.layer;
+        self.layer = layer;
+        draw(self);
+        self.layer = previous;
+    }
+
+    fn item_count(&self, kind: BatchKind) -> usize {
+        match kind {
+            BatchKind::Circle => self.circle_pipeline.item_count(),
+            BatchKind::Polygon => self.polygon_pipeline.item_count(),
+            BatchKind::Quad => self.quad_pipeline.item_count(),
+            BatchKind::Text => self.text_pipeline.item_count(),
+        }
+    }
+
+    fn tracked_end(&self, kind: BatchKind) -> usize {
+        self.batches.iter().rev()
+            .find(|batch| batch.kind == kind)
+            .map(|batch| batch.range.end)
+            .unwrap_or(0)
+    }
+
+    // Records the items added to a pipeline since the last call on the current layer
+    fn track(&mut self, kind: BatchKind) {
+        let start = self.tracked_end(kind);
+        let end = self.item_count(kind);
+        if start == end {
+            return;
+        }
+
+        match self.batches.last_mut() {
+            Some(batch) if batch.kind == kind && batch.layer == self.layer => batch.range.end = end,
+            _ => self.batches.push(Batch { kind, layer: self.layer, range: start..end }),
+        }
+    }
+
+    // Items pushed straight into the pipelines still get drawn, on layer 0
+    fn track_untracked(&mut self) {
+        let layer = self.layer;
+        self.layer = 0;
+        for kind in [BatchKind::Circle, BatchKind::Polygon, BatchKind::Quad, BatchKind::Text] {
+            self.track(kind);
+        }
+        self.layer = layer;
     }
 
     // ====< PRIMITIVES >====
     // Circle
     pub fn draw_circle(&mut self, center: Vector2, radius: f32, color: Color, thickness: f32) {
         self.circle_pipeline.add_circle(Circle::create(center, radius, color, thickness));
+        self.track(BatchKind::Circle);
     }
     pub fn draw_circle_filled(&mut self, center: Vector2, radius: f32, color: Color) {
         self.circle_pipeline.add_circle(Circle::create(center, radius, color, 0.0));
+        self.track(BatchKind::Circle);
     }
 
     // ====< POLYGON >====
@@ -356,23 +555,42 @@ impl Brush {
                 &Box2D { min: a.into(), max: b.into() },
                 lyon::path::Winding::Positive
             );
-        }, Some(Primitive {
-            color: color.into(),
-            angle: 0.0,
-            origin: Vector2::zero().into(),
-            ..Default::default()
-        }))
+        }, Some(Primitive::no_rotation(color.into(), Vector2::zero().into())));
+        self.track(BatchKind::Polygon);
+    }
+
+    pub fn draw_polygon_filled(&mut self, points: &[Vector2], color: Color) {
+        if points.len() < 3 {
+            return;
+        }
+
+        self.draw_path_filled(|builder| {
+            builder.begin(points[0].into());
+            for point in points[1..].iter() {
+                builder.line_to((*point).into());
+            }
+            builder.close();
+        }, Primitive::no_rotation(color.into(), points[0].into()));
+    }
+
+    // Any lyon path, filled with the primitive's color
+    pub fn draw_path_filled(&mut self, path: impl FnOnce(&mut Builder), primitive: Primitive) {
+        self.polygon_pipeline.tesselate_fn(path, Some(primitive));
+        self.track(BatchKind::Polygon);
     }
 
     pub fn draw_rquad_filled(&mut self, a: Vector2, b: Vector2, color: Color, radius: f32) {
         let center = (a + b) / 2.0;
         let size = b - a;
         self.quad_pipeline.add_quad(Quad::create(center, size, color, 0.0, radius, StandardColorPalette::TRANSPARENT, 0.5));
+        self.track(BatchKind::Quad);
     }
 
     // Raw quad
+    #[allow(clippy::too_many_arguments)]
     pub fn _draw_quad_border_raw(&mut self, center: Vector2, size: Vector2, color: Color, border_thickness: f32, border_color: Color, angle: f32, radius: f32) {
         self.quad_pipeline.add_quad(Quad::create(center, size, color, border_thickness, radius, border_color, angle));
+        self.track(BatchKind::Quad);
     }
 
 
@@ -384,6 +602,7 @@ impl Brush {
         let size = Vector2::new(length, thickness);
 
         self.quad_pipeline.add_quad(Quad::create(center, size, color, 0.0, 0.0, StandardColorPalette::TRANSPARENT, angle));
+        self.track(BatchKind::Quad);
     }
 
     pub fn draw_line_rounded(&mut self, a: Vector2, b: Vector2, thickness: f32, color: Color) {
@@ -393,11 +612,50 @@ impl Brush {
         let size = Vector2::new(length, thickness);
 
         self.quad_pipeline.add_quad(Quad::create(center, size, color, 0.0, size.y / 2.0, StandardColorPalette::TRANSPARENT, angle));
+        self.track(BatchKind::Quad);
+    }
+
+    // ====< ARROWS >====
+    // Shaft from `a` to `b` with the head at `b`
+    pub fn draw_arrow(&mut self, a: Vector2, b: Vector2, thickness: f32, head_size: f32, color: Color) {
+        let length = (b - a).length();
+        if length <= f32::EPSILON {
+            return;
+        }
+        let dir = (b - a) / length;
+
+        self.draw_line_rounded(a, b, thickness, color);
+
+        // Two barbs, ARROW_HEAD_ANGLE off the shaft
+        let angle = dir.angle() + std::f32::consts::PI;
+        for side in [-1.0, 1.0] {
+            let barb = Vector2::from_angle(angle + side * ARROW_HEAD_ANGLE) * head_size;
+            self.draw_line_rounded(b, b + barb, thickness, color);
+        }
+    }
+
+    // ====< TEXT >====
+    pub fn draw_text(&mut self, text: &str, position: Vector2, size: f32, color: Color, anchor: TextAnchor, rotation: f32) {
+        self.text_pipeline.add_text(Text::create(text, position, size, color, anchor, rotation));
+        self.track(BatchKind::Text);
+    }
+
+    // Text with a custom font, see `load_font`
+    pub fn add_text(&mut self, text: Text) {
+        self.text_pipeline.add_text(text);
+        self.track(BatchKind::Text);
+    }
+
+    // Loads a .ttf or .otf font, the returned id can be used with `Text::with_font`
+    pub fn load_font(&mut self, data: Vec<u8>) -> Result<FontId, InvalidFont> {
+        self.text_pipeline.load_font(data)
     }
 
     // ====< FLUSH >====
     pub fn flush(&mut self, renderer: &mut Renderer) {
-        renderer.execute_brush(self); 
+        let layer = self.layer;
+        renderer.execute_brush(self);
         self.clear();
+        self.layer = layer;
     }
 }
\ No newline at end of file
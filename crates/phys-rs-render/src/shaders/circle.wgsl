@@ -1,5 +1,11 @@
 struct Globals {
     u_resolution: vec2<f32>,
+    u_camera_position: vec2<f32>,
+    u_camera_zoom: f32,
+    u_camera_rotation: f32,
+    u_metres_per_pixel: f32,
+    u_y_sign: f32,
+    u_origin: vec2<f32>,
 }
 
 @group(0) @binding(0)
@@ -25,14 +31,33 @@ fn to_ndc(position: vec2<f32>) -> vec2<f32> {
     return vec2<f32>(position.x / globals.u_resolution.x * 2.0 - 1.0, 1.0 - position.y / globals.u_resolution.y * 2.0);
 }
 
+// Units and camera transform, see Units::to_pixels and Camera2D::world_to_screen
+fn world_to_screen(position: vec2<f32>) -> vec2<f32> {
+    let pixels = vec2<f32>(position.x, position.y * globals.u_y_sign) / globals.u_metres_per_pixel + globals.u_origin;
+
+    let half = globals.u_resolution / 2.0;
+    let c = cos(globals.u_camera_rotation);
+    let s = sin(globals.u_camera_rotation);
+    let p = pixels - globals.u_camera_position - half;
+    return vec2<f32>(c * p.x + s * p.y, -s * p.x + c * p.y) * globals.u_camera_zoom + half;
+}
+
+// Pixels on screen per world unit
+fn world_scale() -> f32 {
+    return globals.u_camera_zoom / globals.u_metres_per_pixel;
+}
+
 @vertex
 fn vs_main(input: CircleInput) -> CircleOutput {
     var out: CircleOutput;
 
-    var center = to_ndc(input.center);
+    // Everything below is in pixels
+    var screen_center = world_to_screen(input.center);
+    var radius = input.radius * world_scale();
+    var center = to_ndc(screen_center);
 
-    var top_left = center - vec2<f32>(input.radius*2.0 /globals.u_resolution.x, -input.radius*2.0 / globals.u_resolution.y);
-    var bottom_right = center + vec2<f32>(input.radius*2.0 / globals.u_resolution.x, -input.radius*2.0 / globals.u_resolution.y);
+    var top_left = center - vec2<f32>(radius*2.0 /globals.u_resolution.x, -radius*2.0 / globals.u_resolution.y);
+    var bottom_right = center + vec2<f32>(radius*2.0 / globals.u_resolution.x, -radius*2.0 / globals.u_resolution.y);
 
     // Calculate the position of the vertex
     switch (input.vertex_index) {
@@ -45,9 +70,9 @@ fn vs_main(input: CircleInput) -> CircleOutput {
 
     // Pass the input data to the fragment shader
     out.color = input.color;
-    out.radius = input.radius;
-    out.thickness = input.thickness;
-    out.center = input.center;
+    out.radius = radius;
+    out.thickness = input.thickness * world_scale();
+    out.center = screen_center;
 
     return out;
 }
@@ -1,10 +1,33 @@
 struct Globals {
     u_resolution: vec2<f32>,
+    u_camera_position: vec2<f32>,
+    u_camera_zoom: f32,
+    u_camera_rotation: f32,
+    u_metres_per_pixel: f32,
+    u_y_sign: f32,
+    u_origin: vec2<f32>,
 }
 
 @group(0) @binding(0)
 var<uniform> globals: Globals;
 
+// Inverse camera and units transform, see Camera2D::screen_to_world and Units::from_pixels
+fn screen_to_world(position: vec2<f32>) -> vec2<f32> {
+    let half = globals.u_resolution / 2.0;
+    let c = cos(globals.u_camera_rotation);
+    let s = sin(globals.u_camera_rotation);
+    let p = (position - half) / globals.u_camera_zoom;
+    let pixels = vec2<f32>(c * p.x - s * p.y, s * p.x + c * p.y) + half + globals.u_camera_position;
+
+    let world = (pixels - globals.u_origin) * globals.u_metres_per_pixel;
+    return vec2<f32>(world.x, world.y * globals.u_y_sign);
+}
+
+// Always positive, unlike %
+fn wrap(value: vec2<f32>, period: f32) -> vec2<f32> {
+    return value - period * floor(value / period);
+}
+
 struct GridInput {
     @builtin(vertex_index) vertex_index: u32,
     @location(0) top_left: vec2<f32>,
@@ -48,16 +71,21 @@ fn vs_main(input: GridInput) -> GridOutput {
 
 @fragment
 fn fs_main(input: GridOutput) -> @location(0) vec4<f32> {
+    // Lines follow the world, their thickness stays in pixels
+    var uv = screen_to_world(input.position.xy);
+    let zoom = globals.u_camera_zoom / globals.u_metres_per_pixel;
+
     // Big grid
-    var uv = input.position.xy;
-    if uv.x % input.spacing < input.thickness || uv.y % input.spacing < input.thickness {
+    let big = wrap(uv, input.spacing) * zoom;
+    if big.x < input.thickness || big.y < input.thickness {
         return input.color;
     }
 
     // Small grid
     if input.subdivisions > 0u {
-        uv *= f32(input.subdivisions);
-        if uv.x % input.spacing < input.thickness * 3.0 || uv.y % input.spacing < input.thickness * 3.0 {
+        let subdivisions = f32(input.subdivisions);
+        let small = wrap(uv, input.spacing / subdivisions) * zoom * subdivisions;
+        if small.x < input.thickness * 3.0 || small.y < input.thickness * 3.0 {
             return vec4<f32>(input.color.xyz, input.color.w * 0.2);
         }
     }
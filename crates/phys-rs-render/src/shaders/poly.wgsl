@@ -1,19 +1,23 @@
 struct Globals {
     u_resolution: vec2<f32>,
+    u_camera_position: vec2<f32>,
+    u_camera_zoom: f32,
+    u_camera_rotation: f32,
+    u_metres_per_pixel: f32,
+    u_y_sign: f32,
+    u_origin: vec2<f32>,
 }
 
 struct Primitive {
     color: vec4<f32>,
-    angle: f32,
     origin: vec2<f32>,
-
-    count: u32,
+    angle: f32,
 }
 
 @group(0) @binding(0)
 var<uniform> globals: Globals;
 @group(1) @binding(0)
-var<uniform> u_primitives: array<Primitive, 256>;
+var<storage, read> u_primitives: array<Primitive>;
 
 
 struct VertexInput {
@@ -30,6 +34,17 @@ fn to_ndc(position: vec2<f32>) -> vec2<f32> {
     return vec2<f32>(position.x / globals.u_resolution.x * 2.0 - 1.0, 1.0 - position.y / globals.u_resolution.y * 2.0);
 }
 
+// Units and camera transform, see Units::to_pixels and Camera2D::world_to_screen
+fn world_to_screen(position: vec2<f32>) -> vec2<f32> {
+    let pixels = vec2<f32>(position.x, position.y * globals.u_y_sign) / globals.u_metres_per_pixel + globals.u_origin;
+
+    let half = globals.u_resolution / 2.0;
+    let c = cos(globals.u_camera_rotation);
+    let s = sin(globals.u_camera_rotation);
+    let p = pixels - globals.u_camera_position - half;
+    return vec2<f32>(c * p.x + s * p.y, -s * p.x + c * p.y) * globals.u_camera_zoom + half;
+}
+
 @vertex
 fn vs_main(input: VertexInput) -> VertexOutput {
     var output: VertexOutput;
@@ -43,7 +58,7 @@ fn vs_main(input: VertexInput) -> VertexOutput {
         (input.position.x - origin.x) * sin(angle) + (input.position.y - origin.y) * cos(angle) + origin.y,
     );
 
-    output.position = vec4<f32>(to_ndc(rotated), 0.0, 1.0);
+    output.position = vec4<f32>(to_ndc(world_to_screen(rotated)), 0.0, 1.0);
 
     output.color = u_primitives[input.prim_index].color;
 
@@ -1,5 +1,11 @@
 struct Globals {
     u_resolution: vec2<f32>,
+    u_camera_position: vec2<f32>,
+    u_camera_zoom: f32,
+    u_camera_rotation: f32,
+    u_metres_per_pixel: f32,
+    u_y_sign: f32,
+    u_origin: vec2<f32>,
 }
 
 @group(0) @binding(0)
@@ -37,6 +43,22 @@ fn screen_to_ndc(screen: vec2<f32>) -> vec2<f32> {
     return ndc;
 }
 
+// Units and camera transform, see Units::to_pixels and Camera2D::world_to_screen
+fn world_to_screen(position: vec2<f32>) -> vec2<f32> {
+    let pixels = vec2<f32>(position.x, position.y * globals.u_y_sign) / globals.u_metres_per_pixel + globals.u_origin;
+
+    let half = globals.u_resolution / 2.0;
+    let c = cos(globals.u_camera_rotation);
+    let s = sin(globals.u_camera_rotation);
+    let p = pixels - globals.u_camera_position - half;
+    return vec2<f32>(c * p.x + s * p.y, -s * p.x + c * p.y) * globals.u_camera_zoom + half;
+}
+
+// Pixels on screen per world unit
+fn world_scale() -> f32 {
+    return globals.u_camera_zoom / globals.u_metres_per_pixel;
+}
+
 @vertex
 fn vs_main(input: VertexInput) -> VertexOutput {
     var out: VertexOutput;
@@ -52,10 +74,10 @@ fn vs_main(input: VertexInput) -> VertexOutput {
         sin(input.angle), cos(input.angle)
     );
 
-    top_left = rotation_matrix * top_left + input.center;
-    bottom_right = rotation_matrix * bottom_right + input.center;
-    top_right = rotation_matrix * top_right + input.center;
-    bottom_left = rotation_matrix * bottom_left + input.center;
+    top_left = world_to_screen(rotation_matrix * top_left + input.center);
+    bottom_right = world_to_screen(rotation_matrix * bottom_right + input.center);
+    top_right = world_to_screen(rotation_matrix * top_right + input.center);
+    bottom_left = world_to_screen(rotation_matrix * bottom_left + input.center);
 
     // Draw the rectangle (CCW winding order)
     switch input.vertex_index {
@@ -69,13 +91,15 @@ fn vs_main(input: VertexInput) -> VertexOutput {
     }
 
     out.color = input.color;
-    out.border_radius = input.border_radius;
+    // The fragment shader works in pixels
+    out.border_radius = input.border_radius * world_scale();
     out.border_color = input.border_color;
-    out.border_width = input.thickness;
+    out.border_width = input.thickness * world_scale();
 
-    out.center = input.center;
-    out.size = input.size;
-    out.angle = input.angle;
+    out.center = world_to_screen(input.center);
+    out.size = input.size * world_scale();
+    // Flipping y mirrors the rotation
+    out.angle = input.angle * globals.u_y_sign + globals.u_camera_rotation;
 
     out.position = vec4<f32>(screen_to_ndc(out.position.xy), 0.0, 1.0);
 
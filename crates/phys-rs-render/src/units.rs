@@ -0,0 +1,77 @@
+use crate::vec2::Vector2;
+
+// ====< UNITS >====
+/// Maps world units to pixels before the camera is applied.
+/// The default keeps world units equal to pixels with y pointing down.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Units {
+    pub metres_per_pixel: f32,
+    pub y_up: bool,
+    // Where the world origin ends up, in pixels
+    pub origin: Vector2,
+}
+
+impl Default for Units {
+    fn default() -> Self {
+        Self {
+            metres_per_pixel: 1.0,
+            y_up: false,
+            origin: Vector2::zero(),
+        }
+    }
+}
+
+impl Units {
+    pub fn pixels() -> Self {
+        Self::default()
+    }
+
+    // SI units with y up, `origin` is the pixel where (0, 0) m is drawn
+    pub fn metres(metres_per_pixel: f32, origin: Vector2) -> Self {
+        Self {
+            metres_per_pixel,
+            y_up: true,
+            origin,
+        }
+    }
+
+    // 1 for y down, -1 for y up
+    pub fn y_sign(&self) -> f32 {
+        if self.y_up { -1.0 } else { 1.0 }
+    }
+
+    pub fn to_pixels(&self, point: Vector2) -> Vector2 {
+        Vector2::new(point.x, point.y * self.y_sign()) / self.metres_per_pixel + self.origin
+    }
+
+    pub fn from_pixels(&self, point: Vector2) -> Vector2 {
+        let point = (point - self.origin) * self.metres_per_pixel;
+        Vector2::new(point.x, point.y * self.y_sign())
+    }
+
+    pub fn length_to_pixels(&self, length: f32) -> f32 {
+        length / self.metres_per_pixel
+    }
+
+    pub fn length_from_pixels(&self, pixels: f32) -> f32 {
+        pixels * self.metres_per_pixel
+    }
+
+    // Formats a length in metres for labels, e.g. "1 m" or "50 cm"
+    pub fn format_length(length: f32) -> String {
+        let abs = length.abs();
+        let (value, unit) = if abs >= 1000.0 {
+            (length / 1000.0, "km")
+        } else if abs >= 1.0 || abs == 0.0 {
+            (length, "m")
+        } else if abs >= 0.01 {
+            (length * 100.0, "cm")
+        } else {
+            (length * 1000.0, "mm")
+        };
+
+        // At most 3 decimals, without trailing zeros
+        let value = format!("{:.3}", value);
+        format!("{} {}", value.trim_end_matches('0').trim_end_matches('.'), unit)
+    }
+}
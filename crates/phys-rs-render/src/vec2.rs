@@ -1,4 +1,4 @@
-use std::ops::{Add, Mul, AddAssign, Div, Sub, SubAssign, Not, Neg};
+use std::ops::{Add, Mul, AddAssign, Div, Sub, SubAssign, Neg};
 
 use lyon::geom::{euclid::{Point2D, UnknownUnit}, point};
 
@@ -56,6 +56,29 @@ impl Vector2 {
             y: -self.x,
         }
     }
+
+    pub fn dot(&self, other: Vector2) -> f32 {
+        self.x * other.x + self.y * other.y
+    }
+
+    // z component of the 3d cross product
+    pub fn cross(&self, other: Vector2) -> f32 {
+        self.x * other.y - self.y * other.x
+    }
+
+    // Signed angle that rotates `self` onto `other`, in range [-PI, PI]
+    pub fn angle_to(&self, other: Vector2) -> f32 {
+        self.cross(other).atan2(self.dot(other))
+    }
+
+    // Counter-clockwise in a y-up frame
+    pub fn rotate(&self, angle: f32) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        Self {
+            x: self.x * cos - self.y * sin,
+            y: self.x * sin + self.y * cos,
+        }
+    }
 }
 
 impl From<Vector2> for [f32; 2] {
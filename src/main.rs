@@ -1,36 +1,30 @@
-use phys_rs_render::{PhysApp, WindowSettings, Scene, ColorPalette, math::Vector2, PhysRenderable, Renderer, Brush, DataCollector, components::{ui::BasicDataUI, physics::draw_spring}};
+use phys_rs_render::{PhysApp, WindowSettings, Scene, math::Vector2, components::{ui::BasicDataUI, physics::{Wall, SpringStyle}}, physics::{RigidBody, Shape, Spring, UniformGravity}};
 
-struct Mass {
-    radius: f32,
-    position: Vector2,
-    direction: Vector2
-}
-
-impl PhysRenderable for Mass {
-    fn render(&self, brush: &mut Brush, renderer: &mut Renderer, dt: f32, frame: u128) {
-        let length = 50.0 + 60.0 * ((frame as f32 / 20.0).sin() + 1.0);
-        draw_spring(brush, renderer, self.position, self.position + self.direction * length, 1.0, 120.0, 1.0);
-    }
-
-    fn update(&mut self, dt: f32, frame: u128, _data_collector: Option<&mut DataCollector>) {
-        // self.position += self.direction * dt;
-    }
-}
+const GRAVITY: f32 = 500.0;
 
 fn main() {
     let mut app = PhysApp::new(WindowSettings::new("Phys RS Test".to_string(), (800, 600)));
-    app.updates_per_frame = 1000;
+    app.timestep = 1.0 / 240.0;
 
     let mut scene = Scene::new();
-    scene.add_object(Box::new(Mass {
-        radius: 20.0,
-        position: Vector2::new(96.0, 96.0),
-        direction: Vector2::new(96.0, 10.0).normalize(),
-    }));
+    scene.world.add_field(UniformGravity::new(Vector2::new(0.0, GRAVITY)));
+    scene.world.show_fields = true;
+
+    // Block hanging from a spring
+    let ceiling = Wall::create(Vector2::new(300.0, 96.0), Vector2::new(500.0, 96.0), Vector2::new(0.0, 1.0));
+    let anchor = ceiling.center();
+    scene.add_wall(ceiling);
+    scene.add_wall(Wall::create(Vector2::new(100.0, 540.0), Vector2::new(700.0, 540.0), Vector2::new(0.0, -1.0)));
+
+    let position = Vector2::new(460.0, 260.0);
+    let body = scene.add_body(RigidBody::create(Shape::rectangle(40.0, 40.0), 1.0, position));
+    let spring = Spring::create(&scene.world, body, position, None, anchor, 20.0, 120.0).with_name("spring")
+        .with_style(SpringStyle::default().with_tension_colors(1000.0));
+    scene.world.add_spring(spring);
 
     scene.ui = Some(Box::new(BasicDataUI::new()));
 
     app.set_scene(scene);
 
     app.run();
-}
\ No newline at end of file
+}